@@ -0,0 +1,338 @@
+use crate::git::{
+    fast_forward, fetch_error, fetch_remotes, owner_path, prune_worktrees, DirectoryResult,
+};
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum BulkOperation {
+    Fetch,
+    FastForward,
+    Prune,
+    Gc,
+    // runs `command` in every worktree and waits for it, it is split into words like a shell
+    // does and `{folder}` is replaced by the worktree path like for actions
+    Custom { command: String },
+}
+
+// the program and its arguments, quoted like in a shell
+fn command_words(command: &str) -> Result<Vec<String>, String> {
+    let words = shell_words::split(command).map_err(|e| format!("Invalid command: {}", e))?;
+    if words.is_empty() {
+        return Err("No command to run".to_string());
+    }
+    Ok(words)
+}
+
+impl BulkOperation {
+    fn name(&self) -> String {
+        match self {
+            BulkOperation::Fetch => "fetch".to_string(),
+            BulkOperation::FastForward => "fast-forward".to_string(),
+            BulkOperation::Prune => "prune".to_string(),
+            BulkOperation::Gc => "gc".to_string(),
+            BulkOperation::Custom { command } => command_words(command)
+                .map(|words| words[0].clone())
+                .unwrap_or_else(|_| command.clone()),
+        }
+    }
+
+    // fetch, prune and gc work on the repository shared by all its worktrees
+    fn per_repository(&self) -> bool {
+        matches!(
+            self,
+            BulkOperation::Fetch | BulkOperation::Prune | BulkOperation::Gc
+        )
+    }
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct BulkResult {
+    pub path: String,
+    // ok or failed, fast-forward reports its own status: updated, up-to-date, diverged, ...
+    pub status: String,
+    pub message: Option<String>,
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct BulkProgress {
+    // started or finished
+    pub event: String,
+    pub path: String,
+    pub completed: usize,
+    pub total: usize,
+    // only set for finished
+    pub result: Option<BulkResult>,
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct BulkReport {
+    pub operation: String,
+    pub total: usize,
+    // number of results per status
+    pub counts: BTreeMap<String, usize>,
+    pub results: Vec<BulkResult>,
+}
+
+fn bulk_targets(worktrees: &[DirectoryResult], operation: &BulkOperation) -> Vec<String> {
+    let mut targets: Vec<String> = vec![];
+    for worktree in worktrees {
+        let target = if operation.per_repository() {
+            owner_path(&worktree.path).unwrap_or_else(|| worktree.path.clone())
+        } else if worktree.bare {
+            continue;
+        } else {
+            worktree.path.clone()
+        };
+        if !targets.contains(&target) {
+            targets.push(target);
+        }
+    }
+    targets
+}
+
+// a hanging command, e.g. one waiting for a password, would otherwise keep its worker forever
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+// reads the pipe on its own thread so a chatty command can't block on a full pipe
+fn read_pipe(pipe: Option<impl Read + Send + 'static>) -> JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut bytes = vec![];
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut bytes);
+        }
+        bytes
+    })
+}
+
+fn run_command(
+    path: &str,
+    program: &str,
+    arguments: &[String],
+    timeout: Duration,
+) -> Result<String, String> {
+    let mut child = Command::new(program)
+        .args(arguments)
+        .current_dir(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Could not run {}: {}", program, e))?;
+    let stdout = read_pipe(child.stdout.take());
+    let stderr = read_pipe(child.stderr.take());
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(100)),
+            Ok(None) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!(
+                    "{} timed out after {} seconds",
+                    program,
+                    timeout.as_secs()
+                ));
+            }
+            Err(e) => return Err(format!("Could not wait for {}: {}", program, e)),
+        }
+    };
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+    let last_line = |bytes: &[u8]| {
+        String::from_utf8_lossy(bytes)
+            .lines()
+            .rev()
+            .find(|line| !line.trim().is_empty())
+            .unwrap_or_default()
+            .to_string()
+    };
+    if status.success() {
+        Ok(last_line(&stdout))
+    } else {
+        Err(format!("{} ({})", last_line(&stderr), status))
+    }
+}
+
+fn run_operation(path: &str, operation: &BulkOperation) -> BulkResult {
+    let outcome = match operation {
+        BulkOperation::Fetch => fetch_remotes(path).and_then(|results| {
+            if let Some(message) = fetch_error(&results) {
+                return Err(message);
+            }
+            let objects: usize = results.iter().map(|r| r.received_objects).sum();
+            Ok(format!("{} objects received", objects))
+        }),
+        BulkOperation::FastForward => {
+            let result = fast_forward(path);
+            return BulkResult {
+                path: result.path,
+                status: result.status,
+                message: result.message,
+            };
+        }
+        BulkOperation::Prune => prune_worktrees(path).map(|pruned| match pruned.len() {
+            0 => String::new(),
+            _ => "pruned ".to_string() + &pruned.join(", "),
+        }),
+        BulkOperation::Gc => run_command(
+            path,
+            "git",
+            &["gc".to_string(), "--quiet".to_string()],
+            COMMAND_TIMEOUT,
+        ),
+        BulkOperation::Custom { command } => command_words(command).and_then(|words| {
+            // replaced after splitting so a path with spaces stays one argument
+            let words: Vec<String> = words
+                .iter()
+                .map(|word| word.replace("{folder}", path))
+                .collect();
+            run_command(path, &words[0], &words[1..], COMMAND_TIMEOUT)
+        }),
+    };
+
+    let (status, message) = match outcome {
+        Ok(message) => ("ok", Some(message).filter(|m| !m.is_empty())),
+        Err(message) => ("failed", Some(message)),
+    };
+    BulkResult {
+        path: path.to_string(),
+        status: status.to_string(),
+        message,
+    }
+}
+
+// runs `operation` over the scanned worktrees with at most `concurrency` at a time, progress is
+// reported as each one starts and finishes, the report keeps the scan order. A custom command
+// that can't be parsed is refused before anything runs
+pub fn bulk_run(
+    worktrees: &[DirectoryResult],
+    operation: &BulkOperation,
+    concurrency: usize,
+    on_progress: impl Fn(BulkProgress) + Sync,
+) -> Result<BulkReport, String> {
+    if let BulkOperation::Custom { command } = operation {
+        command_words(command)?;
+    }
+    let targets = bulk_targets(worktrees, operation);
+    let total = targets.len();
+    let next = AtomicUsize::new(0);
+    let completed = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<BulkResult>>> = Mutex::new(vec![None; total]);
+
+    std::thread::scope(|scope| {
+        for _ in 0..concurrency.clamp(1, total.max(1)) {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::SeqCst);
+                let Some(path) = targets.get(index) else {
+                    break;
+                };
+                on_progress(BulkProgress {
+                    event: "started".to_string(),
+                    path: path.clone(),
+                    completed: completed.load(Ordering::SeqCst),
+                    total,
+                    result: None,
+                });
+                let result = run_operation(path, operation);
+                on_progress(BulkProgress {
+                    event: "finished".to_string(),
+                    path: path.clone(),
+                    completed: completed.fetch_add(1, Ordering::SeqCst) + 1,
+                    total,
+                    result: Some(result.clone()),
+                });
+                results.lock().unwrap()[index] = Some(result);
+            });
+        }
+    });
+
+    let results: Vec<BulkResult> = results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .flatten()
+        .collect();
+    let mut counts = BTreeMap::new();
+    for result in &results {
+        *counts.entry(result.status.clone()).or_insert(0) += 1;
+    }
+    Ok(BulkReport {
+        operation: operation.name(),
+        total,
+        counts,
+        results,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn stops_commands_that_run_too_long() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().to_str().unwrap();
+        let started = Instant::now();
+
+        let error = run_command(
+            path,
+            "sleep",
+            &["10".to_string()],
+            Duration::from_millis(200),
+        )
+        .unwrap_err();
+        assert!(error.contains("timed out"));
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(
+            run_command(path, "echo", &["done".to_string()], COMMAND_TIMEOUT).unwrap(),
+            "done"
+        );
+    }
+
+    #[test]
+    fn splits_commands_like_a_shell() {
+        assert_eq!(
+            command_words("git  commit -m 'two words' \"{folder}\"").unwrap(),
+            ["git", "commit", "-m", "two words", "{folder}"]
+        );
+        assert!(command_words("   ").is_err());
+        assert!(command_words("echo 'unbalanced")
+            .unwrap_err()
+            .starts_with("Invalid command"));
+    }
+
+    #[test]
+    fn refuses_a_command_with_unbalanced_quotes_before_running() {
+        let operation = BulkOperation::Custom {
+            command: "echo \"unbalanced".to_string(),
+        };
+        let error = bulk_run(&[], &operation, 1, |_| panic!("nothing should run"));
+        assert!(error.is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn keeps_a_folder_with_spaces_as_one_argument() {
+        let dir = tempfile::tempdir().unwrap();
+        let folder = dir.path().join("with  spaces");
+        std::fs::create_dir(&folder).unwrap();
+        let path = folder.to_str().unwrap();
+        let operation = BulkOperation::Custom {
+            command: "echo {folder}".to_string(),
+        };
+
+        let result = run_operation(path, &operation);
+        assert_eq!(result.status, "ok");
+        assert_eq!(result.message.as_deref(), Some(path));
+    }
+}
@@ -0,0 +1,241 @@
+use crate::git::{
+    canonical, open_main_repository, open_repository, open_worktree, repository_path,
+};
+use git2::{
+    build::CheckoutBuilder, BranchType, Reference, Repository, RepositoryState, StatusOptions,
+};
+
+fn head_branch(repo: &Repository) -> Option<String> {
+    let head = repo.head().ok().filter(|head| head.is_branch())?;
+    head.shorthand().map(|s| s.to_string())
+}
+
+// branches checked out in the other worktrees of the repository, with the worktree path
+fn checked_out_elsewhere(path: &str, repo: &Repository) -> Vec<(String, String)> {
+    let Ok(main_repo) = open_main_repository(path) else {
+        return vec![];
+    };
+    let own_path = canonical(&repository_path(repo));
+
+    let mut repositories = vec![];
+    if let Ok(names) = main_repo.worktrees() {
+        for name in names.iter().flatten() {
+            if let Ok(worktree) = main_repo.find_worktree(name) {
+                if let Ok(worktree_repo) = Repository::open_from_worktree(&worktree) {
+                    repositories.push(worktree_repo);
+                }
+            }
+        }
+    }
+    // the HEAD of a bare repository is not a checkout, git allows using its branch elsewhere
+    if !main_repo.is_bare() {
+        repositories.push(main_repo);
+    }
+
+    repositories
+        .iter()
+        .filter(|r| canonical(&repository_path(r)) != own_path)
+        .filter_map(|r| {
+            head_branch(r).map(|branch| (branch, repository_path(r).to_string_lossy().to_string()))
+        })
+        .collect()
+}
+
+// local branches HEAD recently moved to or from, newest first, without the current branch and
+// branches git refuses to check out because another worktree has them
+pub fn recent_branches(path: &str) -> Result<Vec<String>, String> {
+    let repo = open_repository(path)?;
+    let current = head_branch(&repo);
+    let elsewhere: Vec<String> = checked_out_elsewhere(path, &repo)
+        .into_iter()
+        .map(|(branch, _)| branch)
+        .collect();
+
+    // an unborn or freshly cloned HEAD has no reflog yet
+    let Ok(reflog) = repo.reflog("HEAD") else {
+        return Ok(vec![]);
+    };
+    let mut branches: Vec<String> = vec![];
+    for entry in reflog.iter() {
+        let Some(message) = entry.message() else {
+            continue;
+        };
+        let Some(moves) = message.strip_prefix("checkout: moving from ") else {
+            continue;
+        };
+        let Some((from, to)) = moves.split_once(" to ") else {
+            continue;
+        };
+        for branch in [to, from] {
+            if current.as_deref() == Some(branch)
+                || elsewhere.iter().any(|b| b == branch)
+                || branches.iter().any(|b| b == branch)
+                || repo.find_branch(branch, BranchType::Local).is_err()
+            {
+                continue;
+            }
+            branches.push(branch.to_string());
+        }
+    }
+    Ok(branches)
+}
+
+// switches the worktree at `path` to `branch` like `git switch`, a branch that only exists on
+// origin gets a local tracking branch. Local changes are only carried along when asked for and
+// the checkout fails instead of overwriting them
+pub fn checkout_branch(path: &str, branch: &str, carry_changes: bool) -> Result<(), String> {
+    let repo = open_worktree(path)?;
+    if repo.state() != RepositoryState::Clean {
+        return Err("An operation is in progress, finish it from a terminal first".to_string());
+    }
+    if head_branch(&repo).as_deref() == Some(branch) {
+        return Ok(());
+    }
+    if let Some((_, other)) = checked_out_elsewhere(path, &repo)
+        .into_iter()
+        .find(|(b, _)| b == branch)
+    {
+        return Err(format!("{} is already checked out at {}", branch, other));
+    }
+
+    if !carry_changes {
+        let mut options = StatusOptions::new();
+        options.include_untracked(false).include_ignored(false);
+        let changes = repo
+            .statuses(Some(&mut options))
+            .map_err(|e| "status: ".to_string() + e.message())?
+            .len();
+        if changes > 0 {
+            return Err(format!(
+                "{} uncommitted changes, commit them or carry them to {}",
+                changes, branch
+            ));
+        }
+    }
+
+    let mut created_branch = false;
+    let local_branch = match repo.find_branch(branch, BranchType::Local) {
+        Ok(local_branch) => local_branch,
+        Err(_) => {
+            let remote_branch = format!("origin/{}", branch);
+            let commit = repo
+                .find_branch(&remote_branch, BranchType::Remote)
+                .and_then(|b| b.get().peel_to_commit())
+                .map_err(|_| format!("Branch {} not found", branch))?;
+            let mut new_branch = repo
+                .branch(branch, &commit, false)
+                .map_err(|e| format!("Could not create branch {}: {}", branch, e.message()))?;
+            if let Err(e) = new_branch.set_upstream(Some(&remote_branch)) {
+                let _ = new_branch.delete();
+                return Err("upstream: ".to_string() + e.message());
+            }
+            created_branch = true;
+            new_branch
+        }
+    };
+    let result = checkout_reference(&repo, branch, local_branch.into_reference());
+    // don't leave a tracking branch behind when the worktree stayed where it was
+    if result.is_err() && created_branch {
+        if let Ok(mut new_branch) = repo.find_branch(branch, BranchType::Local) {
+            let _ = new_branch.delete();
+        }
+    }
+    result
+}
+
+fn checkout_reference(repo: &Repository, branch: &str, reference: Reference) -> Result<(), String> {
+    let refname = reference.name().ok_or("Invalid branch name")?.to_string();
+    let target = reference
+        .peel_to_commit()
+        .map_err(|e| "commit: ".to_string() + e.message())?;
+
+    // safe checkout keeps local changes and errors on files that would be overwritten
+    let mut checkout = CheckoutBuilder::new();
+    checkout.safe();
+    repo.checkout_tree(target.as_object(), Some(&mut checkout))
+        .map_err(|e| format!("Could not check out {}: {}", branch, e.message()))?;
+    repo.set_head(&refname)
+        .map_err(|e| "set head: ".to_string() + e.message())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::create_worktree;
+    use crate::git::test_support::*;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn checks_out_the_branch_of_a_bare_repository_head() {
+        let dir = tempfile::tempdir().unwrap();
+        let origin_path = dir.path().join("origin");
+        let origin = init_repository(&origin_path);
+        let default_branch = head_branch(&origin).unwrap();
+        let bare_path = dir.path().join("project.git");
+        let url = format!("file://{}", origin_path.to_string_lossy());
+        git2::build::RepoBuilder::new()
+            .bare(true)
+            .clone(&url, &bare_path)
+            .unwrap();
+        let target = dir.path().join("feature");
+        create_worktree(
+            path_str(&bare_path),
+            "feature",
+            Some(path_str(&target)),
+            None,
+            None,
+        )
+        .unwrap();
+
+        checkout_branch(path_str(&target), &default_branch, false).unwrap();
+        let worktree = Repository::open(&target).unwrap();
+        assert_eq!(head_branch(&worktree), Some(default_branch));
+    }
+
+    // a clone of a repository that has an `other` branch changing the README
+    fn clone_with_remote_branch(dir: &Path) -> PathBuf {
+        let origin_path = dir.join("origin");
+        let origin = init_repository(&origin_path);
+        let head = origin.head().unwrap().name().unwrap().to_string();
+        let base = origin.head().unwrap().peel_to_commit().unwrap();
+        origin.branch("other", &base, false).unwrap();
+        switch_to(&origin, "refs/heads/other");
+        write_file(&origin_path, "README.md", "other\n");
+        commit_all(&origin, "other change");
+        switch_to(&origin, &head);
+
+        let clone_path = dir.join("clone");
+        let url = format!("file://{}", origin_path.to_string_lossy());
+        let clone = Repository::clone(&url, &clone_path).unwrap();
+        set_identity(&clone);
+        clone_path
+    }
+
+    #[test]
+    fn creates_a_tracking_branch_for_a_remote_branch() {
+        let dir = tempfile::tempdir().unwrap();
+        let clone_path = clone_with_remote_branch(dir.path());
+
+        checkout_branch(path_str(&clone_path), "other", false).unwrap();
+        let clone = Repository::open(&clone_path).unwrap();
+        assert_eq!(head_branch(&clone).as_deref(), Some("other"));
+        let local_branch = clone.find_branch("other", BranchType::Local).unwrap();
+        let upstream = local_branch.upstream().unwrap();
+        assert_eq!(upstream.name().unwrap(), Some("origin/other"));
+        assert_eq!(read_file(&clone_path, "README.md"), "other\n");
+    }
+
+    #[test]
+    fn removes_the_tracking_branch_when_the_checkout_is_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let clone_path = clone_with_remote_branch(dir.path());
+        write_file(&clone_path, "README.md", "local change\n");
+
+        assert!(checkout_branch(path_str(&clone_path), "other", false).is_err());
+        // carried changes that would be overwritten make the safe checkout fail
+        assert!(checkout_branch(path_str(&clone_path), "other", true).is_err());
+        let clone = Repository::open(&clone_path).unwrap();
+        assert!(clone.find_branch("other", BranchType::Local).is_err());
+        assert_eq!(read_file(&clone_path, "README.md"), "local change\n");
+    }
+}
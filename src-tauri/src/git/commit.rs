@@ -0,0 +1,139 @@
+use crate::git::{open_repository, CommitInfo};
+use git2::RepositoryState;
+
+// commits the index like `git commit`, commit hooks are not run
+pub fn commit(path: &str, message: &str, amend: bool) -> Result<CommitInfo, String> {
+    let repo = open_repository(path)?;
+    if message.trim().is_empty() {
+        return Err("Commit message is empty".to_string());
+    }
+    if repo.state() != RepositoryState::Clean {
+        return Err("An operation is in progress, finish it from a terminal first".to_string());
+    }
+
+    let mut index = repo
+        .index()
+        .map_err(|e| "index: ".to_string() + e.message())?;
+    if index.has_conflicts() {
+        return Err("Resolve the conflicts before committing".to_string());
+    }
+    let tree = index
+        .write_tree()
+        .and_then(|tree| repo.find_tree(tree))
+        .map_err(|e| "tree: ".to_string() + e.message())?;
+
+    // user.name / user.email from the repository config, which includes includeIf sections
+    let signature = repo.signature().map_err(|_| {
+        "No identity configured, set user.name and user.email in your git config".to_string()
+    })?;
+    let parent = repo.head().and_then(|head| head.peel_to_commit()).ok();
+
+    let id = if amend {
+        let parent = parent.ok_or("There is no commit to amend")?;
+        let id = parent
+            .amend(
+                None,
+                None,
+                Some(&signature),
+                None,
+                Some(message),
+                Some(&tree),
+            )
+            .map_err(|e| "amend: ".to_string() + e.message())?;
+        // libgit2 logs amends like new commits, use the reflog message git writes
+        let summary = message.trim().lines().next().unwrap_or_default();
+        repo.head()
+            .and_then(|head| head.resolve())
+            .and_then(|mut reference| {
+                reference.set_target(id, &format!("commit (amend): {}", summary))
+            })
+            .map_err(|e| "amend: ".to_string() + e.message())?;
+        id
+    } else {
+        if parent.as_ref().is_some_and(|p| p.tree_id() == tree.id()) {
+            return Err("Nothing staged to commit".to_string());
+        }
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &parent.iter().collect::<Vec<_>>(),
+        )
+        .map_err(|e| "commit: ".to_string() + e.message())?
+    };
+
+    let commit = repo
+        .find_commit(id)
+        .map_err(|e| "commit: ".to_string() + e.message())?;
+    Ok(CommitInfo::from_commit(&commit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::{stage_files, test_support::*};
+    use git2::Repository;
+
+    fn head_reflog_message(repo: &Repository, reference: &str) -> String {
+        let reflog = repo.reflog(reference).unwrap();
+        reflog.get(0).unwrap().message().unwrap().to_string()
+    }
+
+    #[test]
+    fn commits_the_staged_changes_on_the_current_branch() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = init_repository(dir.path());
+        let branch = repo.head().unwrap().name().unwrap().to_string();
+        let parent = repo.head().unwrap().target().unwrap();
+        write_file(dir.path(), "staged.txt", "staged\n");
+        write_file(dir.path(), "unstaged.txt", "unstaged\n");
+        stage_files(path_str(dir.path()), vec!["staged.txt".to_string()]).unwrap();
+
+        let info = commit(path_str(dir.path()), "add staged", false).unwrap();
+        let head = repo.head().unwrap();
+        assert_eq!(head.name(), Some(branch.as_str()));
+        assert!(head.target().unwrap().to_string().starts_with(&info.id));
+        let new_commit = head.peel_to_commit().unwrap();
+        assert_eq!(new_commit.parent_id(0).unwrap(), parent);
+        assert_eq!(info.summary, "add staged");
+        let tree = new_commit.tree().unwrap();
+        assert!(tree.get_name("staged.txt").is_some());
+        assert!(tree.get_name("unstaged.txt").is_none());
+        assert_eq!(head_reflog_message(&repo, &branch), "commit: add staged");
+    }
+
+    #[test]
+    fn refuses_an_empty_message_or_nothing_staged() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = init_repository(dir.path());
+        let before = repo.head().unwrap().target().unwrap();
+        write_file(dir.path(), "unstaged.txt", "unstaged\n");
+
+        assert!(commit(path_str(dir.path()), "nothing staged", false).is_err());
+        stage_files(path_str(dir.path()), vec!["unstaged.txt".to_string()]).unwrap();
+        assert!(commit(path_str(dir.path()), "  \n", false).is_err());
+        assert_eq!(repo.head().unwrap().target().unwrap(), before);
+    }
+
+    #[test]
+    fn amends_the_last_commit() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = init_repository(dir.path());
+        let branch = repo.head().unwrap().name().unwrap().to_string();
+        write_file(dir.path(), "second.txt", "second\n");
+        commit_all(&repo, "second commit");
+        let before = repo.head().unwrap().peel_to_commit().unwrap();
+        write_file(dir.path(), "README.md", "amended\n");
+        stage_files(path_str(dir.path()), vec!["README.md".to_string()]).unwrap();
+
+        commit(path_str(dir.path()), "amended commit", true).unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_ne!(head.id(), before.id());
+        assert_eq!(head.parent_id(0).unwrap(), before.parent_id(0).unwrap());
+        assert_eq!(head.summary(), Some("amended commit"));
+        let message = head_reflog_message(&repo, &branch);
+        assert!(message.starts_with("commit (amend)"), "{}", message);
+    }
+}
@@ -0,0 +1,147 @@
+use crate::git::open_repository;
+use git2::{Diff, DiffOptions, Patch, Repository};
+
+#[derive(Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct DiffTotals {
+    pub files_changed: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+impl DiffTotals {
+    fn from_diff(diff: &Diff) -> Result<Self, String> {
+        let stats = diff
+            .stats()
+            .map_err(|e| "diff stats: ".to_string() + e.message())?;
+        Ok(DiffTotals {
+            files_changed: stats.files_changed(),
+            insertions: stats.insertions(),
+            deletions: stats.deletions(),
+        })
+    }
+}
+
+// HEAD tree, None when the branch has no commits yet so everything in the index counts as added
+pub fn head_tree(repo: &Repository) -> Option<git2::Tree<'_>> {
+    repo.head().ok()?.peel_to_tree().ok()
+}
+
+// (staged, unstaged) totals like `git diff --cached --shortstat` and `git diff --shortstat`
+pub fn diff_totals(repo: &Repository) -> Result<(DiffTotals, DiffTotals), String> {
+    let mut staged = repo
+        .diff_tree_to_index(head_tree(repo).as_ref(), None, None)
+        .map_err(|e| "diff staged: ".to_string() + e.message())?;
+    // count renames as one changed file instead of a delete and an add
+    staged
+        .find_similar(None)
+        .map_err(|e| "diff renames: ".to_string() + e.message())?;
+    let unstaged = repo
+        .diff_index_to_workdir(None, None)
+        .map_err(|e| "diff unstaged: ".to_string() + e.message())?;
+    Ok((
+        DiffTotals::from_diff(&staged)?,
+        DiffTotals::from_diff(&unstaged)?,
+    ))
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct DiffLine {
+    // ' ' context, '+' added, '-' removed
+    pub origin: String,
+    pub old_lineno: Option<u32>,
+    pub new_lineno: Option<u32>,
+    pub content: String,
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct DiffHunk {
+    pub header: String,
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+    pub lines: Vec<DiffLine>,
+}
+
+#[derive(Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct FileDiff {
+    pub path: String,
+    pub binary: bool,
+    pub hunks: Vec<DiffHunk>,
+    // the same diff as unified patch text
+    pub patch: String,
+}
+
+fn diff_options(file: &str) -> DiffOptions {
+    let mut options = DiffOptions::new();
+    options
+        .pathspec(file)
+        .disable_pathspec_match(true)
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .show_untracked_content(true);
+    options
+}
+
+pub fn get_file_diff(path: &str, file: &str, staged: bool) -> Result<FileDiff, String> {
+    let repo = open_repository(path)?;
+
+    let mut options = diff_options(file);
+    let diff = if staged {
+        repo.diff_tree_to_index(head_tree(&repo).as_ref(), None, Some(&mut options))
+    } else {
+        repo.diff_index_to_workdir(None, Some(&mut options))
+    }
+    .map_err(|e| "diff: ".to_string() + e.message())?;
+
+    let mut file_diff = FileDiff {
+        path: file.to_string(),
+        ..Default::default()
+    };
+    if diff.deltas().len() == 0 {
+        return Ok(file_diff);
+    }
+
+    // a patch is only produced for text files
+    let Some(mut patch) =
+        Patch::from_diff(&diff, 0).map_err(|e| "patch: ".to_string() + e.message())?
+    else {
+        file_diff.binary = true;
+        return Ok(file_diff);
+    };
+    file_diff.binary = patch.delta().flags().is_binary();
+
+    for hunk_index in 0..patch.num_hunks() {
+        let (hunk, line_count) = patch
+            .hunk(hunk_index)
+            .map_err(|e| "hunk: ".to_string() + e.message())?;
+        let mut diff_hunk = DiffHunk {
+            header: String::from_utf8_lossy(hunk.header())
+                .trim_end()
+                .to_string(),
+            old_start: hunk.old_start(),
+            old_lines: hunk.old_lines(),
+            new_start: hunk.new_start(),
+            new_lines: hunk.new_lines(),
+            lines: Vec::with_capacity(line_count),
+        };
+        for line_index in 0..line_count {
+            let line = patch
+                .line_in_hunk(hunk_index, line_index)
+                .map_err(|e| "line: ".to_string() + e.message())?;
+            diff_hunk.lines.push(DiffLine {
+                origin: line.origin().to_string(),
+                old_lineno: line.old_lineno(),
+                new_lineno: line.new_lineno(),
+                content: String::from_utf8_lossy(line.content()).to_string(),
+            });
+        }
+        file_diff.hunks.push(diff_hunk);
+    }
+
+    let buf = patch
+        .to_buf()
+        .map_err(|e| "patch: ".to_string() + e.message())?;
+    file_diff.patch = String::from_utf8_lossy(&buf).to_string();
+    Ok(file_diff)
+}
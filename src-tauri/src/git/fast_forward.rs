@@ -0,0 +1,219 @@
+use crate::git::{main_repository, open_worktree, repository_path};
+use git2::{build::CheckoutBuilder, Repository, RepositoryState, StatusOptions};
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct FastForwardResult {
+    pub path: String,
+    // updated, up-to-date, diverged, dirty, no-upstream or error
+    pub status: String,
+    pub message: Option<String>,
+    // commits the branch moved forward
+    pub commits: usize,
+}
+
+impl FastForwardResult {
+    fn new(path: &str, status: &str, message: Option<String>) -> Self {
+        FastForwardResult {
+            path: path.to_string(),
+            status: status.to_string(),
+            message,
+            commits: 0,
+        }
+    }
+}
+
+fn tracked_changes(repo: &Repository) -> Result<usize, String> {
+    let mut options = StatusOptions::new();
+    options.include_untracked(false).include_ignored(false);
+    repo.statuses(Some(&mut options))
+        .map(|statuses| statuses.len())
+        .map_err(|e| "status: ".to_string() + e.message())
+}
+
+fn try_fast_forward(path: &str) -> Result<FastForwardResult, String> {
+    let repo = open_worktree(path)?;
+    let head = repo
+        .head()
+        .map_err(|e| "head: ".to_string() + e.message())?;
+    if !head.is_branch() {
+        return Ok(FastForwardResult::new(
+            path,
+            "no-upstream",
+            Some("HEAD is not on a branch".to_string()),
+        ));
+    }
+    let branch = git2::Branch::wrap(head);
+    let Ok(upstream) = branch.upstream() else {
+        return Ok(FastForwardResult::new(path, "no-upstream", None));
+    };
+
+    let local = branch.get().target().ok_or("Branch has no target")?;
+    let remote = upstream.get().target().ok_or("Upstream has no target")?;
+    let (ahead, behind) = repo
+        .graph_ahead_behind(local, remote)
+        .map_err(|e| "ahead-behind: ".to_string() + e.message())?;
+    if behind == 0 {
+        return Ok(FastForwardResult::new(path, "up-to-date", None));
+    }
+    if ahead > 0 {
+        return Ok(FastForwardResult::new(
+            path,
+            "diverged",
+            Some(format!("{} ahead, {} behind", ahead, behind)),
+        ));
+    }
+    if repo.state() != RepositoryState::Clean {
+        return Ok(FastForwardResult::new(
+            path,
+            "dirty",
+            Some("An operation is in progress".to_string()),
+        ));
+    }
+    let changes = tracked_changes(&repo)?;
+    if changes > 0 {
+        return Ok(FastForwardResult::new(
+            path,
+            "dirty",
+            Some(format!("{} uncommitted changes", changes)),
+        ));
+    }
+
+    // untracked files that are in the way make the safe checkout fail instead of overwriting them
+    let target = repo
+        .find_commit(remote)
+        .map_err(|e| "commit: ".to_string() + e.message())?;
+    let mut checkout = CheckoutBuilder::new();
+    checkout.safe();
+    repo.checkout_tree(target.as_object(), Some(&mut checkout))
+        .map_err(|e| "Could not update the working directory: ".to_string() + e.message())?;
+
+    let upstream_name = upstream
+        .name()
+        .ok()
+        .flatten()
+        .unwrap_or("upstream")
+        .to_string();
+    let mut reference = branch.into_reference();
+    reference
+        .set_target(remote, &format!("fast-forward: {}", upstream_name))
+        .map_err(|e| "update branch: ".to_string() + e.message())?;
+
+    let mut result = FastForwardResult::new(path, "updated", None);
+    result.commits = behind;
+    Ok(result)
+}
+
+// merges the upstream of the current branch when that is a fast-forward and nothing is
+// uncommitted, like `git merge --ff-only @{u}`. Failures are reported in the result
+pub fn fast_forward(path: &str) -> FastForwardResult {
+    try_fast_forward(path)
+        .unwrap_or_else(|message| FastForwardResult::new(path, "error", Some(message)))
+}
+
+// path of the repository owning the worktree at `path`, worktrees of one repository share
+// their remotes
+pub fn owner_path(path: &str) -> Option<String> {
+    let repo = Repository::open(path).ok()?;
+    let owner = main_repository(&repo)
+        .map(|main_repo| repository_path(&main_repo))
+        .unwrap_or_else(|| repository_path(&repo));
+    Some(owner.to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_support::*;
+    use std::path::Path;
+
+    // a repository on "feature", tracking the local default branch which is one commit ahead
+    fn behind(path: &Path) -> Repository {
+        let repo = init_repository(path);
+        behind_upstream(&repo, path);
+        repo
+    }
+
+    fn behind_upstream(repo: &Repository, path: &Path) {
+        let base_name = repo.head().unwrap().shorthand().unwrap().to_string();
+        let initial = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.branch("feature", &initial, false).unwrap();
+        write_file(path, "README.md", "upstream\n");
+        commit_all(repo, "upstream change");
+
+        switch_to(repo, "refs/heads/feature");
+        repo.find_branch("feature", git2::BranchType::Local)
+            .unwrap()
+            .set_upstream(Some(&base_name))
+            .unwrap();
+    }
+
+    fn head_id(repo: &Repository) -> git2::Oid {
+        repo.head().unwrap().target().unwrap()
+    }
+
+    #[test]
+    fn fast_forwards_to_the_upstream() {
+        let dir = tempfile::tempdir().unwrap();
+        behind(dir.path());
+
+        let result = fast_forward(path_str(dir.path()));
+        assert_eq!(result.status, "updated");
+        assert_eq!(result.commits, 1);
+        assert_eq!(read_file(dir.path(), "README.md"), "upstream\n");
+
+        let result = fast_forward(path_str(dir.path()));
+        assert_eq!(result.status, "up-to-date");
+    }
+
+    #[test]
+    fn refuses_a_branch_that_diverged() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = behind(dir.path());
+        write_file(dir.path(), "feature.txt", "feature\n");
+        let local = commit_all(&repo, "feature change");
+
+        let result = fast_forward(path_str(dir.path()));
+        assert_eq!(result.status, "diverged");
+        assert_eq!(result.message.as_deref(), Some("1 ahead, 1 behind"));
+        assert_eq!(head_id(&repo), local);
+    }
+
+    #[test]
+    fn refuses_uncommitted_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = behind(dir.path());
+        let before = head_id(&repo);
+        write_file(dir.path(), "README.md", "local\n");
+
+        let result = fast_forward(path_str(dir.path()));
+        assert_eq!(result.status, "dirty");
+        assert_eq!(head_id(&repo), before);
+        assert_eq!(read_file(dir.path(), "README.md"), "local\n");
+    }
+
+    #[test]
+    fn skips_a_head_that_is_not_on_a_branch() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = behind(dir.path());
+        let before = head_id(&repo);
+        repo.set_head_detached(before).unwrap();
+
+        let result = fast_forward(path_str(dir.path()));
+        assert_eq!(result.status, "no-upstream");
+        assert_eq!(result.message.as_deref(), Some("HEAD is not on a branch"));
+        assert_eq!(head_id(&repo), before);
+    }
+
+    #[test]
+    fn skips_a_branch_without_upstream() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = behind(dir.path());
+        repo.find_branch("feature", git2::BranchType::Local)
+            .unwrap()
+            .set_upstream(None)
+            .unwrap();
+
+        let result = fast_forward(path_str(dir.path()));
+        assert_eq!(result.status, "no-upstream");
+    }
+}
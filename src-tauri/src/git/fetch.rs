@@ -0,0 +1,180 @@
+use crate::git::open_repository;
+use git2::{Cred, CredentialType, FetchOptions, RemoteCallbacks, Repository};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct FetchResult {
+    pub remote: String,
+    pub received_objects: usize,
+    pub received_bytes: usize,
+    pub error: Option<String>,
+}
+
+// a fetch that takes longer than this is cancelled, its remote is reported as failed
+const FETCH_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+// libgit2 waits forever on a server that stops responding, these apply to every connection.
+// Call once at startup, before any other thread uses libgit2
+pub fn set_network_timeouts() {
+    // SAFETY: libgit2 options are process wide and not synchronized, nothing else runs yet
+    unsafe {
+        let _ = git2::opts::set_server_connect_timeout_in_milliseconds(30_000);
+        let _ = git2::opts::set_server_timeout_in_milliseconds(60_000);
+    }
+}
+
+// try ssh-agent for ssh remotes and the configured git credential helpers for https remotes,
+// each only once as libgit2 keeps calling back as long as we hand out credentials
+pub fn remote_callbacks(config: &git2::Config) -> RemoteCallbacks<'_> {
+    let mut tried_username = false;
+    let mut tried_agent = false;
+    let mut tried_helper = false;
+    let mut tried_default = false;
+    let mut callbacks = RemoteCallbacks::new();
+    callbacks.credentials(move |url, username, allowed| {
+        // asked for before the key when the url has no user name in it
+        if allowed.contains(CredentialType::USERNAME) && !tried_username {
+            tried_username = true;
+            return Cred::username(username.unwrap_or("git"));
+        }
+        if allowed.contains(CredentialType::SSH_KEY) && !tried_agent {
+            tried_agent = true;
+            return Cred::ssh_key_from_agent(username.unwrap_or("git"));
+        }
+        if allowed.contains(CredentialType::USER_PASS_PLAINTEXT) && !tried_helper {
+            tried_helper = true;
+            return Cred::credential_helper(config, url, username);
+        }
+        if allowed.contains(CredentialType::DEFAULT) && !tried_default {
+            tried_default = true;
+            return Cred::default();
+        }
+        Err(git2::Error::from_str("No usable credentials for remote"))
+    });
+    callbacks
+}
+
+// fetches every remote, a remote that fails doesn't keep the others from being fetched
+pub fn fetch_remotes(path: &str) -> Result<Vec<FetchResult>, String> {
+    let repo = open_repository(path)?;
+    let config = repo
+        .config()
+        .map_err(|e| "config: ".to_string() + e.message())?;
+    let remotes = repo
+        .remotes()
+        .map_err(|e| "remotes: ".to_string() + e.message())?;
+
+    Ok(remotes
+        .iter()
+        .flatten()
+        .map(|name| fetch_remote(&repo, &config, name))
+        .collect())
+}
+
+fn fetch_remote(repo: &Repository, config: &git2::Config, name: &str) -> FetchResult {
+    let mut result = FetchResult {
+        remote: name.to_string(),
+        received_objects: 0,
+        received_bytes: 0,
+        error: None,
+    };
+    let mut remote = match repo.find_remote(name) {
+        Ok(remote) => remote,
+        Err(e) => {
+            result.error = Some(format!("remote {}: {}", name, e.message()));
+            return result;
+        }
+    };
+    let deadline = Instant::now() + FETCH_TIMEOUT;
+    let mut callbacks = remote_callbacks(config);
+    // returning false cancels the transfer
+    callbacks.transfer_progress(move |_| Instant::now() < deadline);
+    let mut options = FetchOptions::new();
+    options.remote_callbacks(callbacks);
+
+    // an empty refspec list uses the fetch refspecs configured for the remote
+    match remote.fetch(&[] as &[&str], Some(&mut options), None) {
+        Ok(()) => {
+            let stats = remote.stats();
+            result.received_objects = stats.received_objects();
+            result.received_bytes = stats.received_bytes();
+        }
+        Err(_) if Instant::now() >= deadline => {
+            result.error = Some(format!(
+                "Fetching {} timed out after {} seconds",
+                name,
+                FETCH_TIMEOUT.as_secs()
+            ))
+        }
+        Err(e) => result.error = Some(format!("Could not fetch {}: {}", name, e.message())),
+    }
+    result
+}
+
+// the errors of the remotes that failed to fetch, joined into one message
+pub fn fetch_error(results: &[FetchResult]) -> Option<String> {
+    let errors: Vec<&str> = results.iter().filter_map(|r| r.error.as_deref()).collect();
+    if errors.is_empty() {
+        None
+    } else {
+        Some(errors.join(", "))
+    }
+}
+
+// FETCH_HEAD is written to the git directory of the worktree that fetched, so check the
+// worktree and the common directory and take the most recent one
+pub fn last_fetched(repo: &Repository) -> Option<i64> {
+    [repo.path(), repo.commondir()]
+        .iter()
+        .filter_map(|dir| std::fs::metadata(dir.join("FETCH_HEAD")).ok())
+        .filter_map(|meta| meta.modified().ok())
+        .filter_map(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_secs() as i64)
+        .max()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_support::*;
+
+    #[test]
+    fn fetches_new_commits_from_a_remote() {
+        let dir = tempfile::tempdir().unwrap();
+        let remote_path = dir.path().join("remote");
+        let remote = init_repository(&remote_path);
+        let url = format!("file://{}", remote_path.to_string_lossy());
+        let clone_path = dir.path().join("clone");
+        let clone = Repository::clone(&url, &clone_path).unwrap();
+
+        write_file(&remote_path, "new.txt", "new\n");
+        commit_all(&remote, "new commit");
+
+        let results = fetch_remotes(path_str(&clone_path)).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].remote, "origin");
+        assert!(results[0].error.is_none());
+        assert!(results[0].received_objects > 0);
+        assert!(last_fetched(&clone).is_some());
+    }
+
+    #[test]
+    fn keeps_fetching_after_a_remote_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let remote_path = dir.path().join("remote");
+        init_repository(&remote_path);
+        let url = format!("file://{}", remote_path.to_string_lossy());
+        let clone_path = dir.path().join("clone");
+        let clone = Repository::clone(&url, &clone_path).unwrap();
+        let missing = format!("file://{}", dir.path().join("missing").to_string_lossy());
+        clone.remote("broken", &missing).unwrap();
+
+        let results = fetch_remotes(path_str(&clone_path)).unwrap();
+        assert_eq!(results.len(), 2);
+        let broken = results.iter().find(|r| r.remote == "broken").unwrap();
+        let origin = results.iter().find(|r| r.remote == "origin").unwrap();
+        assert!(broken.error.is_some());
+        assert!(origin.error.is_none());
+        assert!(fetch_error(&results).is_some());
+    }
+}
@@ -0,0 +1,34 @@
+mod bulk;
+mod checkout;
+mod commit;
+mod diff;
+mod fast_forward;
+mod fetch;
+mod move_changes;
+mod repair;
+mod scan;
+mod stage;
+mod stash;
+mod state;
+mod status;
+mod update;
+mod worktree;
+
+#[cfg(test)]
+mod test_support;
+
+pub use bulk::*;
+pub use checkout::*;
+pub use commit::*;
+pub use diff::*;
+pub use fast_forward::*;
+pub use fetch::*;
+pub use move_changes::*;
+pub use repair::*;
+pub use scan::*;
+pub use stage::*;
+pub use stash::*;
+pub use state::*;
+pub use status::*;
+pub use update::*;
+pub use worktree::*;
@@ -0,0 +1,268 @@
+use crate::git::{backup_files, canonical, head_tree, open_worktree};
+use git2::{
+    build::CheckoutBuilder, ApplyLocation, ApplyOptions, Delta, Diff, DiffOptions, Patch,
+    Repository,
+};
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct RejectedHunk {
+    pub path: String,
+    // hunk header, None when the file as a whole can't be applied, e.g. it already exists
+    pub hunk: Option<String>,
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct MoveResult {
+    // false when hunks were rejected, neither worktree is changed then
+    pub moved: bool,
+    pub files: Vec<String>,
+    pub rejected: Vec<RejectedHunk>,
+    // ref holding the source changes as they were before they got moved
+    pub backup: Option<String>,
+}
+
+fn same_repository(source: &Repository, target: &Repository) -> bool {
+    canonical(source.commondir()) == canonical(target.commondir())
+}
+
+fn patch_text(diff: &Diff) -> Result<Vec<u8>, git2::Error> {
+    let mut text = vec![];
+    for index in 0..diff.deltas().len() {
+        if let Some(mut patch) = Patch::from_diff(diff, index)? {
+            text.extend_from_slice(&patch.to_buf()?);
+        }
+    }
+    Ok(text)
+}
+
+// applies only delta `delta`, and only its hunk `hunk` when given, in check mode
+fn applies(target: &Repository, diff: &Diff, delta: usize, hunk: Option<usize>) -> bool {
+    let mut current_delta = 0;
+    let mut current_hunk = 0;
+    let mut options = ApplyOptions::new();
+    options.check(true);
+    options.delta_callback(|_| {
+        current_delta += 1;
+        current_delta - 1 == delta
+    });
+    options.hunk_callback(|_| {
+        current_hunk += 1;
+        hunk.is_none_or(|hunk| current_hunk - 1 == hunk)
+    });
+    target
+        .apply(diff, ApplyLocation::WorkDir, Some(&mut options))
+        .is_ok()
+}
+
+// the files and hunks of `diff` that don't apply to the target worktree
+fn rejected_hunks(target: &Repository, diff: &Diff) -> Vec<RejectedHunk> {
+    let mut rejected = vec![];
+    for (index, delta) in diff.deltas().enumerate() {
+        if applies(target, diff, index, None) {
+            continue;
+        }
+        let path = delta
+            .new_file()
+            .path()
+            .or(delta.old_file().path())
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let hunks = Patch::from_diff(diff, index).ok().flatten();
+        let mut rejected_in_file = vec![];
+        for hunk in 0..hunks.as_ref().map(|p| p.num_hunks()).unwrap_or(0) {
+            if !applies(target, diff, index, Some(hunk)) {
+                let header = hunks
+                    .as_ref()
+                    .and_then(|p| p.hunk(hunk).ok())
+                    .map(|(h, _)| String::from_utf8_lossy(h.header()).trim_end().to_string());
+                rejected_in_file.push(RejectedHunk {
+                    path: path.clone(),
+                    hunk: header,
+                });
+            }
+        }
+        if rejected_in_file.is_empty() {
+            rejected_in_file.push(RejectedHunk { path, hunk: None });
+        }
+        rejected.extend(rejected_in_file);
+    }
+    rejected
+}
+
+// check mode doesn't look at untracked files in the target, the real apply refuses to overwrite
+// them so report those up front
+fn existing_new_files(target: &Repository, diff: &Diff) -> Vec<RejectedHunk> {
+    let Some(workdir) = target.workdir() else {
+        return vec![];
+    };
+    diff.deltas()
+        .filter(|delta| delta.status() == Delta::Added)
+        .filter_map(|delta| delta.new_file().path().map(|p| p.to_path_buf()))
+        .filter(|path| workdir.join(path).symlink_metadata().is_ok())
+        .map(|path| RejectedHunk {
+            path: path.to_string_lossy().to_string(),
+            hunk: None,
+        })
+        .collect()
+}
+
+// moves the staged, unstaged and untracked changes of `source` to the worktree at `target`, which
+// has to belong to the same repository. The changes arrive unstaged, the source is only reset
+// after everything applied and a backup ref of it was made
+pub fn move_changes(source: &str, target: &str) -> Result<MoveResult, String> {
+    let source_repo = open_worktree(source)?;
+    let target_repo = open_worktree(target)?;
+    if !same_repository(&source_repo, &target_repo) {
+        return Err(
+            "Changes can only be moved between worktrees of the same repository".to_string(),
+        );
+    }
+    if source_repo.workdir().map(canonical) == target_repo.workdir().map(canonical) {
+        return Err("Source and target are the same worktree".to_string());
+    }
+    let head = source_repo
+        .head()
+        .and_then(|head| head.peel_to_commit())
+        .map_err(|_| "Nothing committed yet in the source worktree".to_string())?;
+
+    let mut options = DiffOptions::new();
+    options
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .show_untracked_content(true)
+        .show_binary(true);
+    // round trip through the patch text, apply skips untracked deltas but takes new files
+    let diff = source_repo
+        .diff_tree_to_workdir_with_index(head_tree(&source_repo).as_ref(), Some(&mut options))
+        .and_then(|diff| patch_text(&diff))
+        .and_then(|patch| Diff::from_buffer(&patch))
+        .map_err(|e| "diff: ".to_string() + e.message())?;
+    if diff.deltas().len() == 0 {
+        return Err("No uncommitted changes to move".to_string());
+    }
+
+    let mut files: Vec<String> = vec![];
+    for delta in diff.deltas() {
+        for path in [delta.old_file().path(), delta.new_file().path()]
+            .into_iter()
+            .flatten()
+        {
+            let path = path.to_string_lossy().to_string();
+            if !files.contains(&path) {
+                files.push(path);
+            }
+        }
+    }
+
+    let mut rejected = existing_new_files(&target_repo, &diff);
+    let mut check = ApplyOptions::new();
+    check.check(true);
+    if target_repo
+        .apply(&diff, ApplyLocation::WorkDir, Some(&mut check))
+        .is_err()
+    {
+        rejected.extend(rejected_hunks(&target_repo, &diff));
+    }
+    if !rejected.is_empty() {
+        return Ok(MoveResult {
+            moved: false,
+            files,
+            rejected,
+            backup: None,
+        });
+    }
+    // before touching either worktree, a failing apply can leave the target half changed
+    let backup = backup_files(&source_repo, &files, "moving")?;
+    target_repo
+        .apply(&diff, ApplyLocation::WorkDir, None)
+        .map_err(|e| {
+            format!(
+                "Could not apply changes to the target, the source changes are kept in {}: {}",
+                backup,
+                e.message()
+            )
+        })?;
+
+    // the changes are safe in the target now, reset the source to HEAD for the moved files
+    source_repo
+        .reset_default(Some(head.as_object()), &files)
+        .map_err(|e| "reset: ".to_string() + e.message())?;
+    let mut checkout = CheckoutBuilder::new();
+    checkout
+        .force()
+        .remove_untracked(true)
+        .disable_pathspec_match(true);
+    for file in &files {
+        checkout.path(file.as_str());
+    }
+    source_repo
+        .checkout_head(Some(&mut checkout))
+        .map_err(|e| "Could not clean the source worktree: ".to_string() + e.message())?;
+
+    Ok(MoveResult {
+        moved: true,
+        files,
+        rejected: vec![],
+        backup: Some(backup),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::create_worktree;
+    use crate::git::test_support::*;
+    use std::path::{Path, PathBuf};
+
+    // a repository and a `feature` worktree on the same commit
+    fn two_worktrees(dir: &Path) -> (PathBuf, PathBuf) {
+        let repo_path = dir.join("repo");
+        init_repository(&repo_path);
+        let target = dir.join("feature");
+        create_worktree(
+            path_str(&repo_path),
+            "feature",
+            Some(path_str(&target)),
+            None,
+            None,
+        )
+        .unwrap();
+        (repo_path, target)
+    }
+
+    #[test]
+    fn moves_changes_to_another_worktree() {
+        let dir = tempfile::tempdir().unwrap();
+        let (source, target) = two_worktrees(dir.path());
+        write_file(&source, "README.md", "changed\n");
+        write_file(&source, "new.txt", "new\n");
+
+        let result = move_changes(path_str(&source), path_str(&target)).unwrap();
+        assert!(result.moved);
+        assert_eq!(read_file(&target, "README.md"), "changed\n");
+        assert_eq!(read_file(&target, "new.txt"), "new\n");
+        assert_eq!(read_file(&source, "README.md"), "readme\n");
+        assert!(!source.join("new.txt").exists());
+        let repo = Repository::open(&source).unwrap();
+        assert!(repo.revparse_single(&result.backup.unwrap()).is_ok());
+    }
+
+    #[test]
+    fn changes_neither_worktree_when_a_hunk_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let (source, target) = two_worktrees(dir.path());
+        write_file(&source, "README.md", "changed\n");
+        write_file(&source, "new.txt", "new\n");
+        write_file(&target, "README.md", "changed differently\n");
+
+        let result = move_changes(path_str(&source), path_str(&target)).unwrap();
+        assert!(!result.moved);
+        assert_eq!(result.rejected.len(), 1);
+        assert_eq!(result.rejected[0].path, "README.md");
+        assert_eq!(read_file(&source, "README.md"), "changed\n");
+        assert_eq!(read_file(&source, "new.txt"), "new\n");
+        assert_eq!(read_file(&target, "README.md"), "changed differently\n");
+        assert!(!target.join("new.txt").exists());
+    }
+}
@@ -0,0 +1,325 @@
+use crate::git::{canonical, is_bare_repository, open_main_repository, prune_missing_worktrees};
+use git2::Repository;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct BrokenWorktree {
+    // missing-gitdir: the `.git` file points to a git directory that is gone, the main repository
+    // was moved or the worktree was pruned. moved: the main repository still has the old location
+    pub problem: String,
+    // git directory the worktree's `.git` file points to
+    pub gitdir: String,
+}
+
+// errors for commands that open a repository, broken worktrees are reported separately so they
+// can be offered a repair instead of just showing the message
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RepositoryError {
+    BrokenWorktree {
+        message: String,
+        worktree: BrokenWorktree,
+    },
+    Other {
+        message: String,
+    },
+}
+
+// the git directory a linked worktree's `.git` file points to
+fn gitdir_link(path: &Path) -> Option<PathBuf> {
+    let content = std::fs::read_to_string(path.join(".git")).ok()?;
+    let gitdir = content.strip_prefix("gitdir:")?.trim();
+    Some(path.join(gitdir))
+}
+
+// the worktree location recorded by the main repository, `<gitdir>/gitdir` holds its `.git` file,
+// relative to the administrative directory when git wrote it with relative paths
+fn recorded_location(gitdir: &Path) -> Option<PathBuf> {
+    let content = std::fs::read_to_string(gitdir.join("gitdir")).ok()?;
+    Some(gitdir.join(content.trim()))
+}
+
+// linked worktrees have their git directory in `<common dir>/worktrees/<name>`, other `.git` files
+// are a `--separate-git-dir` checkout or a bare `project/.bare` layout, which have no backlink
+fn is_worktree_gitdir(gitdir: &Path) -> bool {
+    if gitdir.is_dir() {
+        return gitdir.join("commondir").is_file();
+    }
+    gitdir
+        .parent()
+        .and_then(|parent| parent.file_name())
+        .is_some_and(|name| name == "worktrees")
+}
+
+pub fn worktree_problem(path: &str) -> Option<BrokenWorktree> {
+    let path = Path::new(path);
+    if !path.join(".git").is_file() {
+        return None;
+    }
+    let gitdir = gitdir_link(path)?;
+    if !is_worktree_gitdir(&gitdir) {
+        return None;
+    }
+    let problem = if !gitdir.is_dir() {
+        "missing-gitdir"
+    } else if recorded_location(&gitdir).map(|l| canonical(&l))
+        != Some(canonical(&path.join(".git")))
+    {
+        "moved"
+    } else {
+        return None;
+    };
+    Some(BrokenWorktree {
+        problem: problem.to_string(),
+        gitdir: gitdir.to_string_lossy().to_string(),
+    })
+}
+
+fn broken_worktree_error(worktree: BrokenWorktree) -> RepositoryError {
+    RepositoryError::BrokenWorktree {
+        message: match worktree.problem.as_str() {
+            "moved" => "Worktree was moved, its repository still points to the old location",
+            _ => "Worktree points to a git directory that no longer exists",
+        }
+        .to_string(),
+        worktree,
+    }
+}
+
+// moved worktrees still open fine, so this is checked up front and not only on errors
+pub fn check_worktree(path: &str) -> Result<(), RepositoryError> {
+    match worktree_problem(path) {
+        Some(worktree) => Err(broken_worktree_error(worktree)),
+        None => Ok(()),
+    }
+}
+
+pub fn repository_error(path: &str, message: String) -> RepositoryError {
+    match worktree_problem(path) {
+        Some(worktree) => broken_worktree_error(worktree),
+        None => RepositoryError::Other { message },
+    }
+}
+
+// names of worktrees registered in `repo` whose directory is gone, only reported for the main
+// repository so every stale entry shows up once
+pub fn stale_worktrees(repo: &Repository) -> Vec<String> {
+    if repo.is_worktree() {
+        return vec![];
+    }
+    let Ok(names) = repo.worktrees() else {
+        return vec![];
+    };
+    names
+        .iter()
+        .flatten()
+        .filter(|name| {
+            repo.find_worktree(name)
+                .and_then(|worktree| worktree.is_prunable(None))
+                .unwrap_or(false)
+        })
+        .map(|name| name.to_string())
+        .collect()
+}
+
+// rewrites the links between a worktree and its main repository, like `git worktree repair`.
+// When the main repository was moved its new location has to be passed as `main_repository`
+pub fn repair_worktree(path: &str, main_repository: Option<&str>) -> Result<(), String> {
+    let Some(broken) = worktree_problem(path) else {
+        return Ok(());
+    };
+    let worktree_path = canonical(Path::new(path));
+    let old_gitdir = PathBuf::from(&broken.gitdir);
+
+    let gitdir = match main_repository.filter(|m| !m.is_empty()) {
+        Some(main_repository) => {
+            let name = old_gitdir
+                .file_name()
+                .ok_or("Could not determine the worktree name")?;
+            let main_repo = open_main_repository(main_repository)?;
+            let gitdir = main_repo.path().join("worktrees").join(name);
+            if !gitdir.is_dir() {
+                return Err(format!(
+                    "{} has no worktree named {}",
+                    main_repository,
+                    name.to_string_lossy()
+                ));
+            }
+            gitdir
+        }
+        None if broken.problem == "moved" => old_gitdir,
+        None => return Err("Main repository not found, select its new location".to_string()),
+    };
+
+    // check the whole layout before writing anything, the main repository may have moved too
+    gitdir
+        .parent()
+        .filter(|worktrees| worktrees.file_name().is_some_and(|n| n == "worktrees"))
+        .and_then(|worktrees| worktrees.parent())
+        .filter(|common_dir| is_bare_repository(common_dir))
+        .ok_or_else(|| {
+            format!(
+                "{} is not a worktree directory of a repository",
+                gitdir.to_string_lossy()
+            )
+        })?;
+    if !gitdir.join("HEAD").is_file() {
+        return Err(format!("{} has no HEAD", gitdir.to_string_lossy()));
+    }
+    // don't take over an entry that belongs to another worktree which still exists
+    if let Some(location) = recorded_location(&gitdir) {
+        if location.is_file() && canonical(&location) != canonical(&worktree_path.join(".git")) {
+            return Err(format!(
+                "{} is used by the worktree at {}",
+                gitdir.to_string_lossy(),
+                location.to_string_lossy()
+            ));
+        }
+    }
+
+    let files = [
+        (
+            worktree_path.join(".git"),
+            format!("gitdir: {}\n", gitdir.to_string_lossy()),
+        ),
+        (
+            gitdir.join("gitdir"),
+            format!("{}\n", worktree_path.join(".git").to_string_lossy()),
+        ),
+        // relative like git writes it, libgit2 writes the absolute path which breaks on a move
+        (gitdir.join("commondir"), "../..\n".to_string()),
+    ];
+    let originals: Vec<Option<Vec<u8>>> = files
+        .iter()
+        .map(|(file, _)| std::fs::read(file).ok())
+        .collect();
+    let written = files.iter().try_for_each(|(file, content)| {
+        std::fs::write(file, content)
+            .map_err(|e| format!("Could not write {}: {}", file.to_string_lossy(), e))
+    });
+    let opened = written.and_then(|_| {
+        Repository::open(path)
+            .map(|_| ())
+            .map_err(|e| "Repaired worktree still can't be opened: ".to_string() + e.message())
+    });
+    if opened.is_err() {
+        // put back what was there, a half repaired worktree is harder to fix by hand
+        for ((file, _), original) in files.iter().zip(originals) {
+            let _ = match original {
+                Some(content) => std::fs::write(file, content),
+                None => std::fs::remove_file(file),
+            };
+        }
+    }
+    opened
+}
+
+// drops the metadata of worktrees whose directory no longer exists, moved worktrees should be
+// repaired first or they lose their link to the repository
+pub fn prune_worktrees(path: &str) -> Result<Vec<String>, String> {
+    let repo = open_main_repository(path)?;
+    prune_missing_worktrees(&repo)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::create_worktree;
+    use crate::git::test_support::*;
+
+    fn linked_worktree(dir: &Path) -> (PathBuf, PathBuf) {
+        let repo_path = dir.join("main");
+        init_repository(&repo_path);
+        let target = dir.join("feature");
+        create_worktree(
+            path_str(&repo_path),
+            "feature",
+            Some(path_str(&target)),
+            None,
+            None,
+        )
+        .unwrap();
+        (repo_path, target)
+    }
+
+    fn head_branch(path: &Path) -> String {
+        let repo = Repository::open(path).unwrap();
+        let head = repo.head().unwrap();
+        head.shorthand().unwrap().to_string()
+    }
+
+    #[test]
+    fn accepts_a_bare_repository_in_the_project_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let origin = dir.path().join("origin");
+        init_repository(&origin);
+        let project = dir.path().join("project");
+        clone_bare(&origin, &project.join(".bare"));
+        write_file(&project, ".git", "gitdir: ./.bare\n");
+
+        assert!(worktree_problem(path_str(&project)).is_none());
+        repair_worktree(path_str(&project), None).unwrap();
+        assert!(!project.join(".bare/gitdir").exists());
+    }
+
+    #[test]
+    fn accepts_a_separate_git_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let work = dir.path().join("work");
+        init_repository(&dir.path().join("repo.git"));
+        std::fs::create_dir_all(&work).unwrap();
+        write_file(&work, ".git", "gitdir: ../repo.git/.git\n");
+
+        assert!(worktree_problem(path_str(&work)).is_none());
+        assert!(Repository::open(&work).is_ok());
+    }
+
+    #[test]
+    fn repairs_a_moved_worktree() {
+        let dir = tempfile::tempdir().unwrap();
+        let (repo_path, target) = linked_worktree(dir.path());
+        let moved = dir.path().join("moved");
+        std::fs::rename(&target, &moved).unwrap();
+
+        let problem = worktree_problem(path_str(&moved)).unwrap();
+        assert_eq!(problem.problem, "moved");
+        repair_worktree(path_str(&moved), None).unwrap();
+        assert!(worktree_problem(path_str(&moved)).is_none());
+        assert_eq!(head_branch(&moved), "feature");
+        let repo = Repository::open(&repo_path).unwrap();
+        let worktree = repo.find_worktree("feature").unwrap();
+        assert!(worktree.validate().is_ok());
+    }
+
+    #[test]
+    fn repairs_a_worktree_of_a_moved_main_repository() {
+        let dir = tempfile::tempdir().unwrap();
+        let (repo_path, target) = linked_worktree(dir.path());
+        let moved = dir.path().join("main-moved");
+        std::fs::rename(&repo_path, &moved).unwrap();
+
+        let problem = worktree_problem(path_str(&target)).unwrap();
+        assert_eq!(problem.problem, "missing-gitdir");
+        assert!(repair_worktree(path_str(&target), None).is_err());
+        repair_worktree(path_str(&target), Some(path_str(&moved))).unwrap();
+        assert!(worktree_problem(path_str(&target)).is_none());
+        assert_eq!(head_branch(&target), "feature");
+        assert_eq!(
+            canonical(Repository::open(&target).unwrap().commondir()),
+            canonical(&moved.join(".git"))
+        );
+    }
+
+    #[test]
+    fn leaves_the_links_alone_when_the_repair_can_not_work() {
+        let dir = tempfile::tempdir().unwrap();
+        let (repo_path, target) = linked_worktree(dir.path());
+        let other = dir.path().join("other");
+        init_repository(&other);
+        std::fs::rename(&repo_path, dir.path().join("main-moved")).unwrap();
+        let link = read_file(&target, ".git");
+
+        assert!(repair_worktree(path_str(&target), Some(path_str(&other))).is_err());
+        assert_eq!(read_file(&target, ".git"), link);
+    }
+}
@@ -0,0 +1,377 @@
+use git2::{Repository, Worktree, WorktreeLockStatus};
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct DirectoryResult {
+    pub name: String,
+    pub path: String,
+    // path relative to the scanned directory, e.g. `org/repo`
+    pub relative_path: String,
+    // path of the main working copy when this is a linked worktree
+    pub main_repository: Option<String>,
+    pub locked: bool,
+    pub lock_reason: Option<String>,
+    pub bare: bool,
+}
+
+impl DirectoryResult {
+    pub fn new(name: String, path: &Path) -> Self {
+        DirectoryResult {
+            relative_path: name.clone(),
+            name,
+            path: path.to_string_lossy().to_string(),
+            main_repository: None,
+            locked: false,
+            lock_reason: None,
+            bare: false,
+        }
+    }
+
+    fn set_lock_status(&mut self, worktree: &Worktree) {
+        if let Ok(WorktreeLockStatus::Locked(reason)) = worktree.is_locked() {
+            self.locked = true;
+            self.lock_reason = reason
+                .map(|r| r.trim().to_string())
+                .filter(|r| !r.is_empty());
+        }
+    }
+}
+
+pub fn open_repository(path: &str) -> Result<Repository, String> {
+    Repository::open(path).map_err(|e| "Could not open repository: ".to_string() + e.message())
+}
+
+// the main repository owning `repo`, None when `repo` is not a linked worktree
+pub fn main_repository(repo: &Repository) -> Option<Repository> {
+    if !repo.is_worktree() {
+        return None;
+    }
+    Repository::open(repo.commondir()).ok()
+}
+
+pub fn repository_path(repo: &Repository) -> PathBuf {
+    let path = repo.workdir().unwrap_or(repo.path());
+    // bare repositories have no workdir, strip the trailing separator by rebuilding the path
+    path.components().collect()
+}
+
+// linked worktrees registered in `repo`, skipping entries whose directory is gone
+pub fn linked_worktrees(repo: &Repository) -> Vec<DirectoryResult> {
+    let mut results = vec![];
+    let main_path = repository_path(repo);
+
+    let names = match repo.worktrees() {
+        Ok(names) => names,
+        Err(_) => return results,
+    };
+    for name in names.iter().flatten() {
+        let Ok(worktree) = repo.find_worktree(name) else {
+            continue;
+        };
+        if worktree.validate().is_err() {
+            continue;
+        }
+
+        let path = worktree.path();
+        let dir_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| name.to_string());
+        let mut result = DirectoryResult::new(dir_name, path);
+        result.main_repository = Some(main_path.to_string_lossy().to_string());
+        result.set_lock_status(&worktree);
+        results.push(result);
+    }
+    results
+}
+
+// a git directory without a checkout, e.g. `project.git` or `project/.bare`
+pub fn is_bare_repository(path: &Path) -> bool {
+    path.join("HEAD").is_file() && path.join("objects").is_dir() && path.join("refs").is_dir()
+}
+
+// resolves symlinks and `..` so paths can be compared, the path itself when it doesn't exist
+pub fn canonical(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+// directories that never contain repositories worth listing
+pub const DEFAULT_SKIP_DIRECTORIES: [&str; 5] =
+    ["node_modules", "target", "vendor", "build", "dist"];
+
+pub struct ScanOptions {
+    pub filter: Option<String>,
+    pub discover_worktrees: bool,
+    // 1 only looks at the direct children of the scan path
+    pub max_depth: usize,
+    pub skip_directories: Vec<String>,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        ScanOptions {
+            filter: None,
+            discover_worktrees: false,
+            max_depth: 1,
+            skip_directories: DEFAULT_SKIP_DIRECTORIES
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+        }
+    }
+}
+
+struct Scanner<'a> {
+    root: &'a Path,
+    options: &'a ScanOptions,
+    filter: Option<fancy_regex::Regex>,
+    seen: Vec<PathBuf>,
+    results: Vec<DirectoryResult>,
+}
+
+impl Scanner<'_> {
+    fn is_match(&self, name: &str) -> bool {
+        match &self.filter {
+            Some(r) => r.is_match(name).unwrap_or_default(),
+            None => true,
+        }
+    }
+
+    fn relative_path(&self, path: &Path) -> Option<String> {
+        path.strip_prefix(self.root)
+            .ok()
+            .map(|p| p.to_string_lossy().replace('\\', "/"))
+    }
+
+    fn walk(&mut self, dir: &Path, depth: usize) {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
+        };
+        let mut entries: Vec<_> = entries.flatten().collect();
+        entries.sort_by_key(|e| e.file_name());
+
+        for entry in entries {
+            let path = entry.path();
+            if !path.is_dir() {
+                continue;
+            }
+
+            // stop descending at repositories, nested ones are submodules or vendored copies
+            let name = entry.file_name().to_string_lossy().to_string();
+            if path.join(".git").exists() || is_bare_repository(&path) {
+                self.add_repository(&name, &path);
+                continue;
+            }
+            if is_bare_repository(&path.join(".bare")) {
+                self.add_repository(&name, &path.join(".bare"));
+                continue;
+            }
+
+            let skip = self.options.skip_directories.contains(&name);
+            let is_symlink = entry.file_type().map(|t| t.is_symlink()).unwrap_or(true);
+            if depth < self.options.max_depth && !skip && !is_symlink {
+                self.walk(&path, depth + 1);
+            }
+        }
+    }
+
+    fn add_repository(&mut self, name: &str, path: &Path) {
+        if self.seen.contains(&canonical(path)) {
+            return;
+        }
+
+        let repo = Repository::open(path).ok();
+        let main_repo = repo.as_ref().and_then(main_repository);
+
+        let bare = repo.as_ref().is_some_and(|r| r.is_bare());
+
+        if self.is_match(name) {
+            let mut result = DirectoryResult::new(name.to_string(), path);
+            result.bare = bare;
+            result.relative_path = self.relative_path(path).unwrap_or(result.name.clone());
+            if let Some(main_repo) = &main_repo {
+                result.main_repository =
+                    Some(repository_path(main_repo).to_string_lossy().to_string());
+                if let Ok(worktree) = Worktree::open_from_repository(repo.as_ref().unwrap()) {
+                    result.set_lock_status(&worktree);
+                }
+            }
+            self.seen.push(canonical(path));
+            self.results.push(result);
+        }
+
+        // enumerate all worktrees of the owning repository, these can live anywhere on disk,
+        // a bare repository is only useful together with its worktrees so always list those
+        if self.options.discover_worktrees || bare {
+            let Some(owner) = main_repo.or(repo) else {
+                return;
+            };
+            for mut worktree in linked_worktrees(&owner) {
+                let worktree_path = canonical(Path::new(&worktree.path));
+                if self.seen.contains(&worktree_path) || !self.is_match(&worktree.name) {
+                    continue;
+                }
+                if let Some(relative_path) = self.relative_path(Path::new(&worktree.path)) {
+                    worktree.relative_path = relative_path;
+                }
+                self.seen.push(worktree_path);
+                self.results.push(worktree);
+            }
+        }
+    }
+}
+
+pub fn scan_directory(path: &str, options: &ScanOptions) -> Result<Vec<DirectoryResult>, String> {
+    // Turn filter into a regex
+    let filter = if let Some(pat) = &options.filter {
+        Some(fancy_regex::Regex::new(pat).map_err(|e| format!("Invalid regex: {}", e))?)
+    } else {
+        None
+    };
+
+    let mut scanner = Scanner {
+        root: Path::new(path),
+        options,
+        filter,
+        seen: vec![],
+        results: vec![],
+    };
+    scanner.walk(Path::new(path), 1);
+    Ok(scanner.results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::create_worktree;
+    use crate::git::test_support::*;
+
+    fn scan(root: &Path, options: &ScanOptions) -> Vec<String> {
+        scan_directory(path_str(root), options)
+            .unwrap()
+            .into_iter()
+            .map(|result| result.relative_path)
+            .collect()
+    }
+
+    fn depth(max_depth: usize) -> ScanOptions {
+        ScanOptions {
+            max_depth,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn scans_up_to_the_maximum_depth() {
+        let dir = tempfile::tempdir().unwrap();
+        init_repository(&dir.path().join("a"));
+        init_repository(&dir.path().join("group/b"));
+        init_repository(&dir.path().join("group/deeper/c"));
+
+        assert_eq!(scan(dir.path(), &depth(1)), vec!["a"]);
+        assert_eq!(scan(dir.path(), &depth(2)), vec!["a", "group/b"]);
+        assert_eq!(
+            scan(dir.path(), &depth(3)),
+            vec!["a", "group/b", "group/deeper/c"]
+        );
+    }
+
+    #[test]
+    fn skips_directories_and_nested_repositories() {
+        let dir = tempfile::tempdir().unwrap();
+        init_repository(&dir.path().join("a"));
+        init_repository(&dir.path().join("a/nested"));
+        init_repository(&dir.path().join("node_modules/package"));
+
+        assert_eq!(scan(dir.path(), &depth(3)), vec!["a"]);
+    }
+
+    #[test]
+    fn filters_on_the_directory_name() {
+        let dir = tempfile::tempdir().unwrap();
+        init_repository(&dir.path().join("api"));
+        init_repository(&dir.path().join("web"));
+        let options = ScanOptions {
+            filter: Some("^a".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(scan(dir.path(), &options), vec!["api"]);
+        let options = ScanOptions {
+            filter: Some("(".to_string()),
+            ..Default::default()
+        };
+        assert!(scan_directory(path_str(dir.path()), &options).is_err());
+    }
+
+    #[test]
+    fn lists_bare_repositories_with_their_worktrees() {
+        let dir = tempfile::tempdir().unwrap();
+        let origin = dir.path().join("origin");
+        init_repository(&origin);
+        let root = dir.path().join("projects");
+        clone_bare(&origin, &root.join("project.git"));
+        // worktrees of a bare repository usually live outside of it
+        let worktree = dir.path().join("elsewhere/feature");
+        create_worktree(
+            path_str(&root.join("project.git")),
+            "feature",
+            Some(path_str(&worktree)),
+            None,
+            None,
+        )
+        .unwrap();
+
+        let results = scan_directory(path_str(&root), &depth(1)).unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results[0].bare);
+        assert_eq!(results[0].name, "project.git");
+        assert!(!results[1].bare);
+        assert_eq!(results[1].path, path_str(&worktree));
+        assert!(results[1].main_repository.is_some());
+    }
+
+    #[test]
+    fn lists_a_bare_directory_under_its_project() {
+        let dir = tempfile::tempdir().unwrap();
+        let origin = dir.path().join("origin");
+        init_repository(&origin);
+        let root = dir.path().join("projects");
+        clone_bare(&origin, &root.join("project/.bare"));
+
+        let results = scan_directory(path_str(&root), &depth(1)).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].name, "project");
+        assert!(results[0].bare);
+    }
+
+    #[test]
+    fn discovers_worktrees_outside_the_scan_path_when_asked() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("projects");
+        let repo_path = root.join("app");
+        init_repository(&repo_path);
+        let worktree = dir.path().join("elsewhere/app-feature");
+        create_worktree(
+            path_str(&repo_path),
+            "feature",
+            Some(path_str(&worktree)),
+            None,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(scan(&root, &depth(1)), vec!["app"]);
+        let options = ScanOptions {
+            discover_worktrees: true,
+            ..Default::default()
+        };
+        let results = scan_directory(path_str(&root), &options).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[1].path, path_str(&worktree));
+        assert_eq!(
+            results[1].main_repository.as_deref(),
+            Some(path_str(&repo_path))
+        );
+    }
+}
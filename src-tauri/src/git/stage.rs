@@ -0,0 +1,436 @@
+use crate::git::{head_tree, open_repository};
+use git2::{
+    build::CheckoutBuilder, ApplyLocation, ApplyOptions, Delta, DiffOptions, Index, IndexEntry,
+    IndexTime, ObjectType, Oid, Repository, Signature, Status,
+};
+use std::path::Path;
+
+pub fn open_worktree(path: &str) -> Result<Repository, String> {
+    let repo = open_repository(path)?;
+    if repo.is_bare() {
+        return Err("Repository has no working directory".to_string());
+    }
+    Ok(repo)
+}
+
+// the configured identity, with a fallback so backups work before user.name is set up
+pub fn signature(repo: &Repository) -> Result<Signature<'static>, String> {
+    repo.signature()
+        .or_else(|_| Signature::now("Worktree Status", "worktree-status@localhost"))
+        .map_err(|e| "signature: ".to_string() + e.message())
+}
+
+pub fn stage_files(path: &str, files: Vec<String>) -> Result<(), String> {
+    let repo = open_worktree(path)?;
+    let workdir = repo.workdir().unwrap().to_path_buf();
+    let mut index = repo
+        .index()
+        .map_err(|e| "index: ".to_string() + e.message())?;
+
+    for file in &files {
+        if workdir.join(file).symlink_metadata().is_ok() {
+            index.add_path(Path::new(file))
+        } else {
+            index.remove_path(Path::new(file))
+        }
+        .map_err(|e| format!("Could not stage {}: {}", file, e.message()))?;
+    }
+    index
+        .write()
+        .map_err(|e| "index write: ".to_string() + e.message())
+}
+
+pub fn unstage_files(path: &str, files: Vec<String>) -> Result<(), String> {
+    let repo = open_worktree(path)?;
+    let head = repo.head().and_then(|head| head.peel(ObjectType::Commit));
+
+    match head {
+        Ok(head) => repo
+            .reset_default(Some(&head), &files)
+            .map_err(|e| "reset: ".to_string() + e.message()),
+        // nothing committed yet, unstaging means removing the entries from the index
+        Err(_) => {
+            let mut index = repo
+                .index()
+                .map_err(|e| "index: ".to_string() + e.message())?;
+            for file in &files {
+                index
+                    .remove_path(Path::new(file))
+                    .map_err(|e| format!("Could not unstage {}: {}", file, e.message()))?;
+            }
+            index
+                .write()
+                .map_err(|e| "index write: ".to_string() + e.message())
+        }
+    }
+}
+
+// apply a single hunk of `file`, the hunk number matches the order returned by get_file_diff
+fn apply_hunk(path: &str, file: &str, hunk: usize, staged: bool) -> Result<(), String> {
+    let repo = open_worktree(path)?;
+
+    let mut options = DiffOptions::new();
+    options.pathspec(file).disable_pathspec_match(true);
+    let diff = if staged {
+        // unstaging applies the reversed index changes back onto the index
+        options.reverse(true);
+        repo.diff_tree_to_index(head_tree(&repo).as_ref(), None, Some(&mut options))
+    } else {
+        options
+            .include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .show_untracked_content(true);
+        repo.diff_index_to_workdir(None, Some(&mut options))
+    }
+    .map_err(|e| "diff: ".to_string() + e.message())?;
+
+    // apply only patches existing index entries, a file that is added or deleted as a whole is a
+    // single hunk, (un)stage the file instead
+    let whole_file = diff.deltas().any(|delta| {
+        matches!(
+            delta.status(),
+            Delta::Added | Delta::Deleted | Delta::Untracked
+        )
+    });
+    if whole_file {
+        if hunk != 0 {
+            return Err(format!("Hunk {} not found in {}", hunk, file));
+        }
+        let files = vec![file.to_string()];
+        return if staged {
+            unstage_files(path, files)
+        } else {
+            stage_files(path, files)
+        };
+    }
+
+    let mut current = 0;
+    let mut apply_options = ApplyOptions::new();
+    apply_options.hunk_callback(|_| {
+        current += 1;
+        current - 1 == hunk
+    });
+    repo.apply(&diff, ApplyLocation::Index, Some(&mut apply_options))
+        .map_err(|e| "apply: ".to_string() + e.message())?;
+    drop(apply_options);
+
+    if current <= hunk {
+        return Err(format!("Hunk {} not found in {}", hunk, file));
+    }
+    Ok(())
+}
+
+pub fn stage_hunk(path: &str, file: &str, hunk: usize) -> Result<(), String> {
+    apply_hunk(path, file, hunk, false)
+}
+
+pub fn unstage_hunk(path: &str, file: &str, hunk: usize) -> Result<(), String> {
+    apply_hunk(path, file, hunk, true)
+}
+
+// token describing the current worktree content of `files`, discard_changes only goes ahead when
+// the files are still exactly what the user confirmed
+pub fn discard_token(path: &str, files: &[String]) -> Result<String, String> {
+    let repo = open_worktree(path)?;
+    let workdir = repo.workdir().unwrap().to_path_buf();
+
+    let mut state = String::new();
+    for file in files {
+        // files that are gone entirely have no status
+        let status = repo.status_file(Path::new(file)).unwrap_or(Status::CURRENT);
+        let content = Oid::hash_file(ObjectType::Blob, workdir.join(file))
+            .map(|oid| oid.to_string())
+            .unwrap_or_else(|_| "missing".to_string());
+        state += &format!("{}\0{}\0{}\n", file, status.bits(), content);
+    }
+    Oid::hash_object(ObjectType::Blob, state.as_bytes())
+        .map(|oid| oid.to_string())
+        .map_err(|e| "token: ".to_string() + e.message())
+}
+
+fn index_entry(path: &str, mode: u32, id: Oid, size: u64) -> IndexEntry {
+    IndexEntry {
+        ctime: IndexTime::new(0, 0),
+        mtime: IndexTime::new(0, 0),
+        dev: 0,
+        ino: 0,
+        mode,
+        uid: 0,
+        gid: 0,
+        file_size: size as u32,
+        id,
+        flags: 0,
+        flags_extended: 0,
+        path: path.as_bytes().to_vec(),
+    }
+}
+
+fn file_mode(meta: &std::fs::Metadata) -> u32 {
+    if meta.file_type().is_symlink() {
+        return 0o120000;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if meta.permissions().mode() & 0o111 != 0 {
+            return 0o100755;
+        }
+    }
+    0o100644
+}
+
+// commit the index with the worktree version of `files` on top to `refs/worktree-status/backups/*`,
+// recover with `git checkout <ref> -- <file>`. `action` ends up in the commit message
+pub fn backup_files(repo: &Repository, files: &[String], action: &str) -> Result<String, String> {
+    let workdir = repo.workdir().unwrap().to_path_buf();
+    let index_tree = repo
+        .index()
+        .and_then(|mut index| index.write_tree())
+        .and_then(|tree| repo.find_tree(tree))
+        .map_err(|e| "Could not read index for backup: ".to_string() + e.message())?;
+
+    let mut backup = Index::new().map_err(|e| "index: ".to_string() + e.message())?;
+    backup
+        .read_tree(&index_tree)
+        .map_err(|e| "index: ".to_string() + e.message())?;
+    for file in files {
+        let full_path = workdir.join(file);
+        let Ok(meta) = full_path.symlink_metadata() else {
+            let _ = backup.remove_path(Path::new(file));
+            continue;
+        };
+        let content = if meta.file_type().is_symlink() {
+            std::fs::read_link(&full_path)
+                .map(|target| target.to_string_lossy().as_bytes().to_vec())
+        } else {
+            std::fs::read(&full_path)
+        }
+        .map_err(|e| format!("Could not read {}: {}", file, e))?;
+        let blob = repo
+            .blob(&content)
+            .map_err(|e| "blob: ".to_string() + e.message())?;
+        backup
+            .add(&index_entry(file, file_mode(&meta), blob, meta.len()))
+            .map_err(|e| format!("Could not back up {}: {}", file, e.message()))?;
+    }
+    let tree = backup
+        .write_tree_to(repo)
+        .and_then(|tree| repo.find_tree(tree))
+        .map_err(|e| "backup tree: ".to_string() + e.message())?;
+
+    let signature = signature(repo)?;
+    let parent = repo.head().and_then(|head| head.peel_to_commit()).ok();
+    let commit = repo
+        .commit(
+            None,
+            &signature,
+            &signature,
+            &format!(
+                "worktree-status: backup before {} {}",
+                action,
+                files.join(", ")
+            ),
+            &tree,
+            &parent.iter().collect::<Vec<_>>(),
+        )
+        .map_err(|e| "backup commit: ".to_string() + e.message())?;
+
+    let name = format!(
+        "refs/worktree-status/backups/{}-{}",
+        signature.when().seconds(),
+        &commit.to_string()[..7]
+    );
+    repo.reference(
+        &name,
+        commit,
+        true,
+        &format!("worktree-status: backup before {}", action),
+    )
+    .map_err(|e| "backup ref: ".to_string() + e.message())?;
+    Ok(name)
+}
+
+// reverts the worktree changes of `files` to the index and deletes untracked files. A backup is
+// committed first, the returned ref name is where the discarded content can be recovered from
+pub fn discard_changes(
+    path: &str,
+    files: Vec<String>,
+    token: &str,
+) -> Result<Option<String>, String> {
+    if files.is_empty() {
+        return Ok(None);
+    }
+    if discard_token(path, &files)? != token {
+        return Err(
+            "Files changed since the discard was confirmed, refusing to discard".to_string(),
+        );
+    }
+
+    let repo = open_worktree(path)?;
+    let workdir = repo.workdir().unwrap().to_path_buf();
+
+    let mut untracked = vec![];
+    let mut changed = vec![];
+    let mut conflicted = vec![];
+    for file in &files {
+        let status = repo
+            .status_file(Path::new(file))
+            .map_err(|e| format!("Could not get status of {}: {}", file, e.message()))?;
+        if status.is_conflicted() {
+            conflicted.push(file.clone());
+        } else if status.is_wt_new() {
+            untracked.push(file.clone());
+        } else if status.intersects(
+            Status::WT_MODIFIED | Status::WT_DELETED | Status::WT_TYPECHANGE | Status::WT_RENAMED,
+        ) {
+            changed.push(file.clone());
+        }
+    }
+    // checking out the index of a conflicted file fails, resolve or abort the merge instead
+    if !conflicted.is_empty() {
+        return Err(format!(
+            "Can't discard conflicted files: {}",
+            conflicted.join(", ")
+        ));
+    }
+    if untracked.is_empty() && changed.is_empty() {
+        return Ok(None);
+    }
+
+    let backup = backup_files(&repo, &files, "discarding")?;
+
+    if !changed.is_empty() {
+        let mut checkout = CheckoutBuilder::new();
+        checkout.force().disable_pathspec_match(true);
+        for file in &changed {
+            checkout.path(file.as_str());
+        }
+        repo.checkout_index(None, Some(&mut checkout))
+            .map_err(|e| "checkout: ".to_string() + e.message())?;
+    }
+    for file in &untracked {
+        std::fs::remove_file(workdir.join(file))
+            .map_err(|e| format!("Could not remove {}: {}", file, e))?;
+    }
+    Ok(Some(backup))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_support::*;
+
+    fn staged_content(repo: &Repository, file: &str) -> Option<String> {
+        // staging happens through another handle, don't look at a stale copy
+        let mut index = repo.index().unwrap();
+        index.read(true).unwrap();
+        let entry = index.get_path(Path::new(file), 0)?;
+        let blob = repo.find_blob(entry.id).unwrap();
+        Some(String::from_utf8_lossy(blob.content()).to_string())
+    }
+
+    #[test]
+    fn stages_and_unstages_a_single_hunk() {
+        let dir = tempfile::tempdir().unwrap();
+        let lines: Vec<String> = (1..=20).map(|n| format!("line {}\n", n)).collect();
+        let repo = init_repository(dir.path());
+        write_file(dir.path(), "README.md", &lines.concat());
+        commit_all(&repo, "twenty lines");
+        let mut changed = lines.clone();
+        changed[0] = "first\n".to_string();
+        changed[19] = "last\n".to_string();
+        write_file(dir.path(), "README.md", &changed.concat());
+
+        stage_hunk(path_str(dir.path()), "README.md", 1).unwrap();
+        let mut expected = lines.clone();
+        expected[19] = "last\n".to_string();
+        assert_eq!(
+            staged_content(&repo, "README.md").unwrap(),
+            expected.concat()
+        );
+
+        unstage_hunk(path_str(dir.path()), "README.md", 0).unwrap();
+        assert_eq!(staged_content(&repo, "README.md").unwrap(), lines.concat());
+        assert!(stage_hunk(path_str(dir.path()), "README.md", 5).is_err());
+    }
+
+    #[test]
+    fn stages_the_hunk_of_an_added_or_deleted_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = init_repository(dir.path());
+        write_file(dir.path(), "new.txt", "new\n");
+
+        stage_hunk(path_str(dir.path()), "new.txt", 0).unwrap();
+        assert_eq!(staged_content(&repo, "new.txt").unwrap(), "new\n");
+
+        unstage_hunk(path_str(dir.path()), "new.txt", 0).unwrap();
+        assert!(staged_content(&repo, "new.txt").is_none());
+        assert!(dir.path().join("new.txt").exists());
+
+        std::fs::remove_file(dir.path().join("README.md")).unwrap();
+        stage_hunk(path_str(dir.path()), "README.md", 0).unwrap();
+        assert!(staged_content(&repo, "README.md").is_none());
+        unstage_hunk(path_str(dir.path()), "README.md", 0).unwrap();
+        assert_eq!(staged_content(&repo, "README.md").unwrap(), "readme\n");
+    }
+
+    #[test]
+    fn discards_changes_and_keeps_a_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = init_repository(dir.path());
+        write_file(dir.path(), "README.md", "changed\n");
+        write_file(dir.path(), "new.txt", "new\n");
+        let files = vec!["README.md".to_string(), "new.txt".to_string()];
+        let token = discard_token(path_str(dir.path()), &files).unwrap();
+
+        let backup = discard_changes(path_str(dir.path()), files, &token)
+            .unwrap()
+            .unwrap();
+        assert_eq!(read_file(dir.path(), "README.md"), "readme\n");
+        assert!(!dir.path().join("new.txt").exists());
+        let backup_tree = repo
+            .revparse_single(&backup)
+            .unwrap()
+            .peel_to_tree()
+            .unwrap();
+        assert!(backup_tree.get_name("new.txt").is_some());
+    }
+
+    #[test]
+    fn refuses_when_files_changed_after_confirming() {
+        let dir = tempfile::tempdir().unwrap();
+        init_repository(dir.path());
+        write_file(dir.path(), "README.md", "changed\n");
+        let files = vec!["README.md".to_string()];
+        let token = discard_token(path_str(dir.path()), &files).unwrap();
+        write_file(dir.path(), "README.md", "changed again\n");
+
+        assert!(discard_changes(path_str(dir.path()), files, &token).is_err());
+        assert_eq!(read_file(dir.path(), "README.md"), "changed again\n");
+    }
+
+    #[test]
+    fn refuses_to_discard_conflicted_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = init_repository(dir.path());
+        let head = repo.head().unwrap().name().unwrap().to_string();
+        let base = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.branch("other", &base, false).unwrap();
+        write_file(dir.path(), "README.md", "ours\n");
+        commit_all(&repo, "ours");
+        switch_to(&repo, "refs/heads/other");
+        write_file(dir.path(), "README.md", "theirs\n");
+        commit_all(&repo, "theirs");
+        switch_to(&repo, &head);
+        let other = repo.find_reference("refs/heads/other").unwrap();
+        let other = repo.reference_to_annotated_commit(&other).unwrap();
+        repo.merge(&[&other], None, None).unwrap();
+        let conflicted = read_file(dir.path(), "README.md");
+
+        let files = vec!["README.md".to_string()];
+        let token = discard_token(path_str(dir.path()), &files).unwrap();
+        let error = discard_changes(path_str(dir.path()), files, &token).unwrap_err();
+        assert!(error.contains("conflicted"));
+        assert_eq!(read_file(dir.path(), "README.md"), conflicted);
+    }
+}
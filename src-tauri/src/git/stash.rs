@@ -0,0 +1,56 @@
+use crate::git::open_repository;
+use git2::Repository;
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct StashEntry {
+    pub index: usize,
+    pub message: String,
+    // branch the stash was created on, parsed from the `WIP on <branch>: ...` message
+    pub branch: Option<String>,
+    // seconds since epoch
+    pub time: i64,
+}
+
+fn stash_branch(message: &str) -> Option<String> {
+    let rest = message
+        .strip_prefix("WIP on ")
+        .or_else(|| message.strip_prefix("On "))?;
+    rest.split_once(':').map(|(branch, _)| branch.to_string())
+}
+
+// stashes live in the common git directory so every worktree of a repository sees the same list,
+// only the ones made on `branch` are counted. The stash reflog holds the same entries as
+// stash_foreach without needing a mutable repository
+pub fn stash_count(repo: &Repository, branch: &str) -> usize {
+    let Ok(reflog) = repo.reflog("refs/stash") else {
+        return 0;
+    };
+    reflog
+        .iter()
+        .filter(|entry| entry.message().and_then(stash_branch).as_deref() == Some(branch))
+        .count()
+}
+
+pub fn list_stashes(path: &str) -> Result<Vec<StashEntry>, String> {
+    let mut repo = open_repository(path)?;
+
+    let mut stashes = vec![];
+    repo.stash_foreach(|index, message, oid| {
+        stashes.push((index, message.to_string(), *oid));
+        true
+    })
+    .map_err(|e| "stashes: ".to_string() + e.message())?;
+
+    Ok(stashes
+        .into_iter()
+        .map(|(index, message, oid)| StashEntry {
+            index,
+            branch: stash_branch(&message),
+            time: repo
+                .find_commit(oid)
+                .map(|c| c.time().seconds())
+                .unwrap_or_default(),
+            message,
+        })
+        .collect())
+}
@@ -0,0 +1,386 @@
+use crate::git::{
+    diff_totals, last_fetched, open_repository, stale_worktrees, stash_count, DiffTotals,
+};
+use git2::{
+    Commit, DescribeFormatOptions, DescribeOptions, Oid, Repository, RepositoryState, StatusOptions,
+};
+
+#[derive(Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct BranchState {
+    pub branch: String,
+    // branch, detached, tag or unborn
+    pub head_kind: String,
+    // abbreviated id of the commit HEAD points to
+    pub commit_id: Option<String>,
+    pub describe: Option<String>,
+    pub last_commit: Option<CommitInfo>,
+    pub upstream: Upstream,
+    pub ahead: usize,
+    pub behind: usize,
+    // base branch feature work is compared against, e.g. `origin/main`
+    pub base_ref: Option<String>,
+    pub base_ahead: usize,
+    pub base_behind: usize,
+    // seconds since epoch, taken from FETCH_HEAD
+    pub last_fetched: Option<i64>,
+
+    // totals, the index and worktree counters below hold the `git status -s` breakdown
+    pub staged: usize,
+    pub added: usize,
+    pub modified: usize,
+    pub deleted: usize,
+    pub untracked: usize,
+    pub conflict: usize,
+    pub index: StatusCounts,
+    pub worktree: StatusCounts,
+    pub staged_diff: DiffTotals,
+    pub unstaged_diff: DiffTotals,
+    // stashes made on this branch, the stash list itself is shared by all worktrees
+    pub stashes: usize,
+
+    pub bare: bool,
+    // local branches, only filled for bare repositories which have no checkout to report on
+    pub branches: Vec<String>,
+    // registered worktrees whose directory is gone, only filled for the main repository
+    pub stale_worktrees: Vec<String>,
+
+    // operation in progress: clean, merge, rebase-interactive, cherry-pick, bisect, ...
+    pub state: String,
+    // rebase / am progress, step N of M
+    pub state_step: Option<usize>,
+    pub state_total: Option<usize>,
+}
+
+#[derive(Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct StatusCounts {
+    // added to the index, or untracked for the worktree
+    pub new: usize,
+    pub modified: usize,
+    pub deleted: usize,
+    pub renamed: usize,
+    pub typechange: usize,
+}
+
+impl StatusCounts {
+    fn add(&mut self, new: bool, modified: bool, deleted: bool, renamed: bool, typechange: bool) {
+        self.new += new as usize;
+        self.modified += modified as usize;
+        self.deleted += deleted as usize;
+        self.renamed += renamed as usize;
+        self.typechange += typechange as usize;
+    }
+}
+
+// untracked files are listed one by one and renames are detected in the index and worktree
+pub fn status_options() -> StatusOptions {
+    let mut options = StatusOptions::new();
+    options
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .include_ignored(false)
+        .renames_head_to_index(true)
+        .renames_index_to_workdir(true);
+    options
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct Upstream {
+    // e.g. `origin/feature-x`
+    pub name: Option<String>,
+    // tracking, gone (configured but the remote branch was deleted) or none
+    pub status: String,
+}
+
+impl Default for Upstream {
+    fn default() -> Self {
+        Upstream {
+            name: None,
+            status: "none".to_string(),
+        }
+    }
+}
+
+// upstream of a local branch as configured in `branch.<name>.remote` / `branch.<name>.merge`
+pub fn branch_upstream(repo: &Repository, branch_name: &str) -> Upstream {
+    let shorthand = |name: &str| {
+        name.strip_prefix("refs/remotes/")
+            .or_else(|| name.strip_prefix("refs/heads/"))
+            .unwrap_or(name)
+            .to_string()
+    };
+
+    let refname = format!("refs/heads/{}", branch_name);
+    if let Ok(upstream) = repo.branch_upstream_name(&refname) {
+        let Some(upstream) = upstream.as_str() else {
+            return Upstream::default();
+        };
+        let status = if repo.find_reference(upstream).is_ok() {
+            "tracking"
+        } else {
+            "gone"
+        };
+        return Upstream {
+            name: Some(shorthand(upstream)),
+            status: status.to_string(),
+        };
+    }
+
+    // the remote itself may have been removed while the merge config stayed behind
+    let merge = repo
+        .config()
+        .and_then(|c| c.get_string(&format!("branch.{}.merge", branch_name)));
+    match merge {
+        Ok(merge) => Upstream {
+            name: Some(shorthand(&merge)),
+            status: "gone".to_string(),
+        },
+        Err(_) => Upstream::default(),
+    }
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct CommitInfo {
+    pub id: String,
+    pub summary: String,
+    pub author: String,
+    // seconds since epoch
+    pub time: i64,
+}
+
+impl CommitInfo {
+    pub fn from_commit(commit: &Commit) -> Self {
+        CommitInfo {
+            id: commit
+                .as_object()
+                .short_id()
+                .ok()
+                .and_then(|id| id.as_str().map(|s| s.to_string()))
+                .unwrap_or_else(|| commit.id().to_string()),
+            summary: commit.summary().unwrap_or_default().to_string(),
+            author: commit.author().name().unwrap_or_default().to_string(),
+            time: commit.time().seconds(),
+        }
+    }
+}
+
+fn state_name(state: RepositoryState) -> &'static str {
+    match state {
+        RepositoryState::Clean => "clean",
+        RepositoryState::Merge => "merge",
+        RepositoryState::Revert => "revert",
+        RepositoryState::RevertSequence => "revert-sequence",
+        RepositoryState::CherryPick => "cherry-pick",
+        RepositoryState::CherryPickSequence => "cherry-pick-sequence",
+        RepositoryState::Bisect => "bisect",
+        RepositoryState::Rebase => "rebase",
+        RepositoryState::RebaseInteractive => "rebase-interactive",
+        RepositoryState::RebaseMerge => "rebase-merge",
+        RepositoryState::ApplyMailbox => "apply-mailbox",
+        RepositoryState::ApplyMailboxOrRebase => "apply-mailbox-or-rebase",
+    }
+}
+
+fn read_git_file(repo: &Repository, file: &str) -> Option<String> {
+    std::fs::read_to_string(repo.path().join(file))
+        .ok()
+        .map(|s| s.trim().to_string())
+}
+
+// step N of M for a rebase or am in progress, read from the state directories git keeps
+fn operation_progress(repo: &Repository) -> (Option<usize>, Option<usize>) {
+    let read_number = |file: &str| read_git_file(repo, file).and_then(|s| s.parse().ok());
+    if repo.path().join("rebase-merge").is_dir() {
+        return (
+            read_number("rebase-merge/msgnum"),
+            read_number("rebase-merge/end"),
+        );
+    }
+    if repo.path().join("rebase-apply").is_dir() {
+        return (
+            read_number("rebase-apply/next"),
+            read_number("rebase-apply/last"),
+        );
+    }
+    (None, None)
+}
+
+// the branch being rebased, HEAD is detached while a rebase is running
+fn rebase_head_name(repo: &Repository) -> Option<String> {
+    let head_name = read_git_file(repo, "rebase-merge/head-name")
+        .or_else(|| read_git_file(repo, "rebase-apply/head-name"))?;
+    Some(
+        head_name
+            .strip_prefix("refs/heads/")
+            .unwrap_or(&head_name)
+            .to_string(),
+    )
+}
+
+// name of the branch HEAD points to when it has no commits yet
+fn unborn_branch_name(repo: &Repository) -> String {
+    repo.find_reference("HEAD")
+        .ok()
+        .and_then(|head| head.symbolic_target().map(|t| t.to_string()))
+        .map(|t| t.strip_prefix("refs/heads/").unwrap_or(&t).to_string())
+        .unwrap_or_default()
+}
+
+// `git describe --tags` style label, e.g. `v1.2.0-3-g1a2b3c4`
+fn describe_head(repo: &Repository, exact_match: bool) -> Option<String> {
+    let mut options = DescribeOptions::new();
+    options.describe_tags();
+    if exact_match {
+        options.max_candidates_tags(0);
+    }
+    repo.describe(&options)
+        .ok()?
+        .format(Some(DescribeFormatOptions::new().abbreviated_size(7)))
+        .ok()
+}
+
+// the configured base ref, or the remote default branch from `refs/remotes/origin/HEAD`
+pub fn resolve_base_ref(repo: &Repository, base_ref: Option<&str>) -> Option<(String, Oid)> {
+    let name = match base_ref.filter(|b| !b.is_empty()) {
+        Some(base_ref) => base_ref.to_string(),
+        None => {
+            let origin_head = repo.find_reference("refs/remotes/origin/HEAD").ok()?;
+            let target = origin_head.symbolic_target()?;
+            target
+                .strip_prefix("refs/remotes/")
+                .unwrap_or(target)
+                .to_string()
+        }
+    };
+    let commit = repo.revparse_single(&name).ok()?.peel_to_commit().ok()?;
+    Some((name, commit.id()))
+}
+
+pub fn get_branch_state(path: &str, base_ref: Option<&str>) -> Result<BranchState, String> {
+    // use libgit crate to get the branch state
+    let repo = open_repository(path)?;
+    let bare = repo.is_bare();
+    let (state_step, state_total) = operation_progress(&repo);
+    let mut branch_state = BranchState {
+        bare,
+        branches: if bare { local_branches(&repo)? } else { vec![] },
+        stale_worktrees: stale_worktrees(&repo),
+        state: state_name(repo.state()).to_string(),
+        state_step,
+        state_total,
+        last_fetched: last_fetched(&repo),
+        ..Default::default()
+    };
+
+    let Ok(head) = repo.head() else {
+        branch_state.head_kind = "unborn".to_string();
+        branch_state.branch = unborn_branch_name(&repo);
+        return Ok(branch_state);
+    };
+
+    branch_state.last_commit = head
+        .peel_to_commit()
+        .ok()
+        .map(|c| CommitInfo::from_commit(&c));
+    branch_state.commit_id = branch_state.last_commit.as_ref().map(|c| c.id.clone());
+    branch_state.describe = describe_head(&repo, false);
+
+    if head.is_branch() {
+        branch_state.head_kind = "branch".to_string();
+        branch_state.branch = head
+            .shorthand()
+            .ok_or("Could not get branchname!")?
+            .to_string();
+    } else if let Some(tag) = describe_head(&repo, true) {
+        branch_state.head_kind = "tag".to_string();
+        branch_state.branch = tag;
+    } else {
+        branch_state.head_kind = "detached".to_string();
+        branch_state.branch = branch_state.commit_id.clone().unwrap_or_default();
+    }
+    if !head.is_branch() && branch_state.state.starts_with("rebase") {
+        if let Some(rebase_branch) = rebase_head_name(&repo) {
+            branch_state.branch = rebase_branch;
+        }
+    }
+    branch_state.stashes = stash_count(&repo, &branch_state.branch);
+
+    // get git branch
+    if head.is_branch() {
+        let branch = repo
+            .find_branch(&branch_state.branch, git2::BranchType::Local)
+            .map_err(|e| format!("branch {}: {}", branch_state.branch, e.message()))?;
+        branch_state.upstream = branch_upstream(&repo, &branch_state.branch);
+        if let Ok(upstream) = branch.upstream() {
+            (branch_state.ahead, branch_state.behind) = repo
+                .graph_ahead_behind(
+                    branch.get().target().unwrap(),
+                    upstream.get().target().unwrap(),
+                )
+                .map_err(|e| "ahead-behind: ".to_string() + e.message())?;
+        }
+    }
+
+    if let (Some((base_name, base)), Some(target)) =
+        (resolve_base_ref(&repo, base_ref), head.target())
+    {
+        (branch_state.base_ahead, branch_state.base_behind) = repo
+            .graph_ahead_behind(target, base)
+            .map_err(|e| "base ahead-behind: ".to_string() + e.message())?;
+        branch_state.base_ref = Some(base_name);
+    }
+
+    // bare repositories have no working directory to report on
+    if bare {
+        return Ok(branch_state);
+    }
+
+    let statuses = repo
+        .statuses(Some(&mut status_options()))
+        .map_err(|e| "statuses: ".to_string() + e.message())?;
+
+    for entry in statuses.iter() {
+        let status = entry.status();
+        branch_state.index.add(
+            status.is_index_new(),
+            status.is_index_modified(),
+            status.is_index_deleted(),
+            status.is_index_renamed(),
+            status.is_index_typechange(),
+        );
+        branch_state.worktree.add(
+            status.is_wt_new(),
+            status.is_wt_modified(),
+            status.is_wt_deleted(),
+            status.is_wt_renamed(),
+            status.is_wt_typechange(),
+        );
+        if status.is_index_new()
+            || status.is_index_modified()
+            || status.is_index_deleted()
+            || status.is_index_renamed()
+            || status.is_index_typechange()
+        {
+            branch_state.staged += 1;
+        }
+        if status.is_conflicted() {
+            branch_state.conflict += 1;
+        }
+    }
+    (branch_state.staged_diff, branch_state.unstaged_diff) = diff_totals(&repo)?;
+    branch_state.added = branch_state.index.new;
+    branch_state.untracked = branch_state.worktree.new;
+    branch_state.modified = branch_state.worktree.modified;
+    branch_state.deleted = branch_state.worktree.deleted;
+
+    Ok(branch_state)
+}
+
+fn local_branches(repo: &Repository) -> Result<Vec<String>, String> {
+    let branches = repo
+        .branches(Some(git2::BranchType::Local))
+        .map_err(|e| "branches: ".to_string() + e.message())?;
+    Ok(branches
+        .flatten()
+        .filter_map(|(branch, _)| branch.name().ok().flatten().map(|n| n.to_string()))
+        .collect())
+}
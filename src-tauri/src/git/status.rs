@@ -0,0 +1,101 @@
+use crate::git::{open_repository, status_options};
+use git2::Status;
+use std::path::Path;
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct FileStatus {
+    pub path: String,
+    // `git status -s` letters: A, M, D, R, T, U (conflict) and ? (untracked), None when unchanged
+    pub index_status: Option<String>,
+    pub worktree_status: Option<String>,
+    pub renamed_from: Option<String>,
+    // size in bytes of the file in the worktree, None when it was deleted
+    pub size: Option<u64>,
+}
+
+fn index_status(status: Status) -> Option<&'static str> {
+    if status.is_conflicted() {
+        Some("U")
+    } else if status.is_index_new() {
+        Some("A")
+    } else if status.is_index_modified() {
+        Some("M")
+    } else if status.is_index_deleted() {
+        Some("D")
+    } else if status.is_index_renamed() {
+        Some("R")
+    } else if status.is_index_typechange() {
+        Some("T")
+    } else {
+        None
+    }
+}
+
+fn worktree_status(status: Status) -> Option<&'static str> {
+    if status.is_conflicted() {
+        Some("U")
+    } else if status.is_wt_new() {
+        Some("?")
+    } else if status.is_wt_modified() {
+        Some("M")
+    } else if status.is_wt_deleted() {
+        Some("D")
+    } else if status.is_wt_renamed() {
+        Some("R")
+    } else if status.is_wt_typechange() {
+        Some("T")
+    } else {
+        None
+    }
+}
+
+fn path_string(path: Option<&Path>) -> Option<String> {
+    path.map(|p| p.to_string_lossy().replace('\\', "/"))
+}
+
+pub fn get_file_statuses(path: &str) -> Result<Vec<FileStatus>, String> {
+    let repo = open_repository(path)?;
+    let workdir = repo
+        .workdir()
+        .ok_or("Repository has no working directory")?
+        .to_path_buf();
+    let statuses = repo
+        .statuses(Some(&mut status_options()))
+        .map_err(|e| "statuses: ".to_string() + e.message())?;
+
+    let mut files = vec![];
+    for entry in statuses.iter() {
+        let status = entry.status();
+        let head_to_index = entry.head_to_index();
+        let index_to_workdir = entry.index_to_workdir();
+
+        // for renames `entry.path()` is the old name, the deltas know where the file went
+        let file_path = index_to_workdir
+            .as_ref()
+            .and_then(|d| path_string(d.new_file().path()))
+            .or_else(|| {
+                head_to_index
+                    .as_ref()
+                    .and_then(|d| path_string(d.new_file().path()))
+            })
+            .unwrap_or_else(|| String::from_utf8_lossy(entry.path_bytes()).to_string());
+        let renamed_from = if status.is_index_renamed() {
+            head_to_index.and_then(|d| path_string(d.old_file().path()))
+        } else if status.is_wt_renamed() {
+            index_to_workdir.and_then(|d| path_string(d.old_file().path()))
+        } else {
+            None
+        };
+
+        files.push(FileStatus {
+            size: std::fs::symlink_metadata(workdir.join(&file_path))
+                .ok()
+                .map(|m| m.len()),
+            path: file_path,
+            index_status: index_status(status).map(|s| s.to_string()),
+            worktree_status: worktree_status(status).map(|s| s.to_string()),
+            renamed_from,
+        });
+    }
+    Ok(files)
+}
@@ -0,0 +1,71 @@
+use git2::{IndexAddOption, Oid, Repository};
+use std::path::Path;
+
+// a repository with one commit and an identity to commit with
+pub fn init_repository(path: &Path) -> Repository {
+    let repo = Repository::init(path).unwrap();
+    set_identity(&repo);
+    write_file(path, "README.md", "readme\n");
+    commit_all(&repo, "initial commit");
+    repo
+}
+
+// libgit2 doesn't fall back to the environment for the signature like git does
+pub fn set_identity(repo: &Repository) {
+    let mut config = repo.config().unwrap();
+    config.set_str("user.name", "Test").unwrap();
+    config.set_str("user.email", "test@example.com").unwrap();
+}
+
+pub fn write_file(dir: &Path, name: &str, content: &str) {
+    let path = dir.join(name);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).unwrap();
+    }
+    std::fs::write(path, content).unwrap();
+}
+
+pub fn read_file(dir: &Path, name: &str) -> String {
+    std::fs::read_to_string(dir.join(name)).unwrap()
+}
+
+// stages everything in the working directory and commits it on HEAD
+pub fn commit_all(repo: &Repository, message: &str) -> Oid {
+    let mut index = repo.index().unwrap();
+    index.add_all(["*"], IndexAddOption::DEFAULT, None).unwrap();
+    index.update_all(["*"], None).unwrap();
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let signature = repo.signature().unwrap();
+    let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+    let parents: Vec<&git2::Commit> = parent.iter().collect();
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        message,
+        &tree,
+        &parents,
+    )
+    .unwrap()
+}
+
+// a bare clone of `origin`, like the ones worktrees are usually added to
+pub fn clone_bare(origin: &Path, path: &Path) -> Repository {
+    let url = format!("file://{}", origin.to_string_lossy());
+    git2::build::RepoBuilder::new()
+        .bare(true)
+        .clone(&url, path)
+        .unwrap()
+}
+
+// points HEAD at `reference` and makes the working directory match it
+pub fn switch_to(repo: &Repository, reference: &str) {
+    repo.set_head(reference).unwrap();
+    repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force()))
+        .unwrap();
+}
+
+pub fn path_str(path: &Path) -> &str {
+    path.to_str().unwrap()
+}
@@ -0,0 +1,432 @@
+use crate::git::{open_main_repository, open_repository, repository_path, resolve_base_ref};
+use git2::{
+    build::CheckoutBuilder, ErrorCode, Index, Oid, RebaseOptions, Repository, RepositoryState,
+    Signature, StatusOptions,
+};
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct UpdateResult {
+    pub path: String,
+    pub branch: Option<String>,
+    // updated, up-to-date, dirty, conflict, skipped or error
+    pub status: String,
+    pub message: Option<String>,
+    // files that conflicted, the branch is left as it was
+    pub conflicts: Vec<String>,
+}
+
+impl UpdateResult {
+    fn new(path: &str, branch: Option<&str>, status: &str, message: Option<String>) -> Self {
+        UpdateResult {
+            path: path.to_string(),
+            branch: branch.map(|b| b.to_string()),
+            status: status.to_string(),
+            message,
+            conflicts: vec![],
+        }
+    }
+}
+
+fn conflicted_files(index: &Index) -> Vec<String> {
+    let Ok(conflicts) = index.conflicts() else {
+        return vec![];
+    };
+    conflicts
+        .flatten()
+        .filter_map(|c| c.our.or(c.their).or(c.ancestor))
+        .map(|entry| String::from_utf8_lossy(&entry.path).to_string())
+        .collect()
+}
+
+// files that would conflict when merging `base` into `head`, computed in memory. Rebasing replays
+// commit by commit and can conflict differently, this is the same prediction `git merge-tree` gives
+pub fn merge_conflicts(repo: &Repository, head: Oid, base: Oid) -> Result<Vec<String>, String> {
+    let head_commit = repo
+        .find_commit(head)
+        .map_err(|e| "commit: ".to_string() + e.message())?;
+    let base_commit = repo
+        .find_commit(base)
+        .map_err(|e| "commit: ".to_string() + e.message())?;
+    let index = repo
+        .merge_commits(&head_commit, &base_commit, None)
+        .map_err(|e| "Could not merge: ".to_string() + e.message())?;
+    Ok(conflicted_files(&index))
+}
+
+pub fn predict_conflicts(path: &str, base_ref: Option<&str>) -> Result<Vec<String>, String> {
+    let repo = open_repository(path)?;
+    let (_, base) = resolve_base_ref(&repo, base_ref).ok_or("No base branch to compare with")?;
+    let head = repo
+        .head()
+        .ok()
+        .and_then(|head| head.target())
+        .ok_or("Nothing committed yet")?;
+    merge_conflicts(&repo, head, base)
+}
+
+// replays the commits of `head` not in `base` on top of it in memory, returns the new tip or the
+// conflicting files. Nothing is written to the worktree or the branch
+fn rebase_in_memory(
+    repo: &Repository,
+    head: Oid,
+    base: Oid,
+    committer: &Signature,
+) -> Result<Result<Oid, Vec<String>>, String> {
+    let branch = repo
+        .find_annotated_commit(head)
+        .map_err(|e| "annotated commit: ".to_string() + e.message())?;
+    let upstream = repo
+        .find_annotated_commit(base)
+        .map_err(|e| "annotated commit: ".to_string() + e.message())?;
+    let mut options = RebaseOptions::new();
+    options.inmemory(true);
+    let mut rebase = repo
+        .rebase(Some(&branch), Some(&upstream), None, Some(&mut options))
+        .map_err(|e| "Could not start rebase: ".to_string() + e.message())?;
+
+    let mut tip = base;
+    while let Some(operation) = rebase.next() {
+        let outcome = operation
+            .map_err(|e| "rebase step: ".to_string() + e.message())
+            .and_then(|_| {
+                rebase
+                    .inmemory_index()
+                    .map_err(|e| "rebase index: ".to_string() + e.message())
+            })
+            .map(|index| conflicted_files(&index));
+        let conflicts = match outcome {
+            Ok(conflicts) => conflicts,
+            Err(message) => {
+                let _ = rebase.abort();
+                return Err(message);
+            }
+        };
+        if !conflicts.is_empty() {
+            let _ = rebase.abort();
+            return Ok(Err(conflicts));
+        }
+        match rebase.commit(None, committer, None) {
+            Ok(id) => tip = id,
+            // the change is already in the base branch
+            Err(e) if e.code() == ErrorCode::Applied => {}
+            Err(e) => {
+                let _ = rebase.abort();
+                return Err("rebase commit: ".to_string() + e.message());
+            }
+        }
+    }
+    let _ = rebase.abort();
+    Ok(Ok(tip))
+}
+
+// merges `base` into `head` in memory, returns the merge commit or the conflicting files
+fn merge_in_memory(
+    repo: &Repository,
+    head: Oid,
+    base: Oid,
+    message: &str,
+    signature: &Signature,
+) -> Result<Result<Oid, Vec<String>>, String> {
+    let head_commit = repo
+        .find_commit(head)
+        .map_err(|e| "commit: ".to_string() + e.message())?;
+    let base_commit = repo
+        .find_commit(base)
+        .map_err(|e| "commit: ".to_string() + e.message())?;
+    let mut index = repo
+        .merge_commits(&head_commit, &base_commit, None)
+        .map_err(|e| "Could not merge: ".to_string() + e.message())?;
+    if index.has_conflicts() {
+        return Ok(Err(conflicted_files(&index)));
+    }
+    let tree = index
+        .write_tree_to(repo)
+        .and_then(|tree| repo.find_tree(tree))
+        .map_err(|e| "merge tree: ".to_string() + e.message())?;
+    repo.commit(
+        None,
+        signature,
+        signature,
+        message,
+        &tree,
+        &[&head_commit, &base_commit],
+    )
+    .map(Ok)
+    .map_err(|e| "merge commit: ".to_string() + e.message())
+}
+
+fn update_worktree(
+    repo: &Repository,
+    base_name: &str,
+    base: Oid,
+    merge: bool,
+) -> Result<UpdateResult, String> {
+    let path = repository_path(repo).to_string_lossy().to_string();
+    let head = repo
+        .head()
+        .map_err(|e| "head: ".to_string() + e.message())?;
+    if !head.is_branch() {
+        return Ok(UpdateResult::new(
+            &path,
+            None,
+            "skipped",
+            Some("HEAD is not on a branch".to_string()),
+        ));
+    }
+    let branch_name = head.shorthand().unwrap_or_default().to_string();
+    let branch = Some(branch_name.as_str());
+    // the local copy of the base branch itself is for fast-forward
+    if branch_name == base_name
+        || base_name
+            .split_once('/')
+            .is_some_and(|(_, b)| b == branch_name)
+    {
+        return Ok(UpdateResult::new(&path, branch, "skipped", None));
+    }
+
+    let head_id = head.target().ok_or("Branch has no target")?;
+    let (ahead, behind) = repo
+        .graph_ahead_behind(head_id, base)
+        .map_err(|e| "ahead-behind: ".to_string() + e.message())?;
+    if behind == 0 {
+        return Ok(UpdateResult::new(&path, branch, "up-to-date", None));
+    }
+
+    let mut options = StatusOptions::new();
+    options.include_untracked(false).include_ignored(false);
+    let changes = repo
+        .statuses(Some(&mut options))
+        .map_err(|e| "status: ".to_string() + e.message())?
+        .len();
+    if repo.state() != RepositoryState::Clean || changes > 0 {
+        return Ok(UpdateResult::new(
+            &path,
+            branch,
+            "dirty",
+            Some(format!("{} uncommitted changes", changes)),
+        ));
+    }
+
+    let signature = repo.signature().map_err(|_| {
+        "No identity configured, set user.name and user.email in your git config".to_string()
+    })?;
+    let outcome = if ahead == 0 {
+        Ok(base)
+    } else if merge {
+        let message = format!("Merge {} into {}", base_name, branch_name);
+        merge_in_memory(repo, head_id, base, &message, &signature)?
+    } else {
+        rebase_in_memory(repo, head_id, base, &signature)?
+    };
+    let new_head = match outcome {
+        Ok(new_head) => new_head,
+        Err(conflicts) => {
+            let mut result = UpdateResult::new(
+                &path,
+                branch,
+                "conflict",
+                Some(format!("{} conflicting files", conflicts.len())),
+            );
+            result.conflicts = conflicts;
+            return Ok(result);
+        }
+    };
+
+    // nothing changed so far, a failing checkout leaves the branch where it was
+    let target = repo
+        .find_commit(new_head)
+        .map_err(|e| "commit: ".to_string() + e.message())?;
+    let mut checkout = CheckoutBuilder::new();
+    checkout.safe();
+    repo.checkout_tree(target.as_object(), Some(&mut checkout))
+        .map_err(|e| "Could not update the working directory: ".to_string() + e.message())?;
+    let action = if merge { "merge" } else { "rebase" };
+    if let Err(e) = head.resolve().and_then(|mut reference| {
+        reference.set_target(new_head, &format!("{}: onto {}", action, base_name))
+    }) {
+        // the branch didn't move, put the working directory back to match it
+        let mut checkout = CheckoutBuilder::new();
+        checkout.force();
+        let restored = repo
+            .find_commit(head_id)
+            .and_then(|commit| repo.checkout_tree(commit.as_object(), Some(&mut checkout)));
+        let message = "Could not update the branch: ".to_string() + e.message();
+        return Err(match restored {
+            Ok(()) => message,
+            Err(e) => format!(
+                "{}, restoring the working directory failed too: {}",
+                message,
+                e.message()
+            ),
+        });
+    }
+
+    Ok(UpdateResult::new(
+        &path,
+        branch,
+        "updated",
+        Some(format!("{} commits from {}", behind, base_name)),
+    ))
+}
+
+// rebases, or merges when `merge` is set, the branch of every clean worktree of the repository
+// onto the base ref. Each worktree is updated in memory first so a conflict leaves it untouched
+pub fn update_worktrees(
+    path: &str,
+    base_ref: Option<&str>,
+    merge: bool,
+) -> Result<Vec<UpdateResult>, String> {
+    let main_repo = open_main_repository(path)?;
+    let (base_name, base) =
+        resolve_base_ref(&main_repo, base_ref).ok_or("No base branch to update onto")?;
+
+    let mut repositories = vec![];
+    if let Ok(names) = main_repo.worktrees() {
+        for name in names.iter().flatten() {
+            let Ok(worktree) = main_repo.find_worktree(name) else {
+                continue;
+            };
+            if worktree.validate().is_err() {
+                continue;
+            }
+            if let Ok(repo) = Repository::open_from_worktree(&worktree) {
+                repositories.push(repo);
+            }
+        }
+    }
+    if !main_repo.is_bare() {
+        repositories.insert(0, main_repo);
+    }
+
+    Ok(repositories
+        .iter()
+        .map(|repo| {
+            update_worktree(repo, &base_name, base, merge).unwrap_or_else(|message| {
+                let path = repository_path(repo).to_string_lossy().to_string();
+                let head = repo.head().ok();
+                let branch = head.as_ref().and_then(|head| head.shorthand());
+                UpdateResult::new(&path, branch, "error", Some(message))
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::create_worktree;
+    use crate::git::test_support::*;
+    use std::path::{Path, PathBuf};
+
+    // a repository whose base branch and `feature` worktree both moved on from the first commit,
+    // returns the repository path, the base branch name and the worktree path
+    fn diverged(dir: &Path, base_file: &str, feature_file: &str) -> (PathBuf, String, PathBuf) {
+        let repo_path = dir.join("repo");
+        let repo = init_repository(&repo_path);
+        let base_name = repo.head().unwrap().shorthand().unwrap().to_string();
+        let target = dir.join("feature");
+        create_worktree(
+            path_str(&repo_path),
+            "feature",
+            Some(path_str(&target)),
+            None,
+            None,
+        )
+        .unwrap();
+
+        write_file(&repo_path, base_file, "base\n");
+        commit_all(&repo, "base change");
+        let worktree = Repository::open(&target).unwrap();
+        write_file(&target, feature_file, "feature\n");
+        commit_all(&worktree, "feature change");
+        (repo_path, base_name, target)
+    }
+
+    fn feature_result(results: &[UpdateResult]) -> &UpdateResult {
+        results
+            .iter()
+            .find(|r| r.branch.as_deref() == Some("feature"))
+            .unwrap()
+    }
+
+    fn head_id(path: &Path) -> Oid {
+        Repository::open(path)
+            .unwrap()
+            .head()
+            .unwrap()
+            .target()
+            .unwrap()
+    }
+
+    #[test]
+    fn rebases_a_worktree_onto_the_base_branch() {
+        let dir = tempfile::tempdir().unwrap();
+        let (repo_path, base_name, target) = diverged(dir.path(), "base.txt", "feature.txt");
+
+        let results = update_worktrees(path_str(&repo_path), Some(&base_name), false).unwrap();
+        assert_eq!(feature_result(&results).status, "updated");
+        assert_eq!(read_file(&target, "base.txt"), "base\n");
+        assert_eq!(read_file(&target, "feature.txt"), "feature\n");
+        let repo = Repository::open(&repo_path).unwrap();
+        let base = repo.revparse_single(&base_name).unwrap().id();
+        assert!(repo.graph_descendant_of(head_id(&target), base).unwrap());
+    }
+
+    #[test]
+    fn leaves_dirty_and_conflicting_worktrees_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let (repo_path, base_name, target) = diverged(dir.path(), "README.md", "README.md");
+        let before = head_id(&target);
+
+        let results = update_worktrees(path_str(&repo_path), Some(&base_name), false).unwrap();
+        let result = feature_result(&results);
+        assert_eq!(result.status, "conflict");
+        assert_eq!(result.conflicts, vec!["README.md".to_string()]);
+        assert_eq!(head_id(&target), before);
+        assert_eq!(read_file(&target, "README.md"), "feature\n");
+
+        write_file(&target, "README.md", "uncommitted\n");
+        let results = update_worktrees(path_str(&repo_path), Some(&base_name), false).unwrap();
+        assert_eq!(feature_result(&results).status, "dirty");
+        assert_eq!(head_id(&target), before);
+        assert_eq!(read_file(&target, "README.md"), "uncommitted\n");
+    }
+
+    #[test]
+    fn restores_the_working_directory_when_the_branch_can_not_move() {
+        let dir = tempfile::tempdir().unwrap();
+        let (repo_path, base_name, target) = diverged(dir.path(), "base.txt", "feature.txt");
+        let before = head_id(&target);
+        // another git process holding the branch lock makes updating the ref fail
+        write_file(&repo_path, ".git/refs/heads/feature.lock", "");
+
+        let results = update_worktrees(path_str(&repo_path), Some(&base_name), false).unwrap();
+        assert_eq!(feature_result(&results).status, "error");
+        assert_eq!(head_id(&target), before);
+        assert!(!target.join("base.txt").exists());
+        let worktree = Repository::open(&target).unwrap();
+        assert!(worktree.statuses(None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn predicts_the_files_that_would_conflict() {
+        let dir = tempfile::tempdir().unwrap();
+        let (_, base_name, target) = diverged(dir.path(), "README.md", "README.md");
+        let before = head_id(&target);
+
+        let conflicts = predict_conflicts(path_str(&target), Some(&base_name)).unwrap();
+        assert_eq!(conflicts, vec!["README.md".to_string()]);
+        assert_eq!(head_id(&target), before);
+        assert_eq!(read_file(&target, "README.md"), "feature\n");
+    }
+
+    #[test]
+    fn predicts_no_conflicts_for_a_clean_merge() {
+        let dir = tempfile::tempdir().unwrap();
+        let (_, base_name, target) = diverged(dir.path(), "base.txt", "feature.txt");
+
+        let conflicts = predict_conflicts(path_str(&target), Some(&base_name)).unwrap();
+        assert!(conflicts.is_empty());
+        assert!(!target.join("base.txt").exists());
+    }
+}
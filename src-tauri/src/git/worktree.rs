@@ -0,0 +1,423 @@
+use crate::git::{
+    get_branch_state, main_repository, open_repository, open_worktree, repository_path,
+    resolve_base_ref, status_options, DirectoryResult,
+};
+use git2::{
+    BranchType, Oid, Repository, Worktree, WorktreeAddOptions, WorktreeLockStatus,
+    WorktreePruneOptions,
+};
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_WORKTREE_TEMPLATE: &str = "{repo_parent}/{repo}-{branch}";
+
+// the main repository for `path`, which may be any of its worktrees
+pub fn open_main_repository(path: &str) -> Result<Repository, String> {
+    let repo = open_repository(path)?;
+    Ok(main_repository(&repo).unwrap_or(repo))
+}
+
+// project directory of a repository, for a bare `project/.bare` or `project.git` that is `project`
+fn project_path(repo: &Repository) -> PathBuf {
+    let path = repository_path(repo);
+    if repo.is_bare() && path.file_name().is_some_and(|n| n == ".bare") {
+        return path.parent().map(|p| p.to_path_buf()).unwrap_or(path);
+    }
+    path
+}
+
+// fills in {repo_parent}, {repo} and {branch}, slashes in branch names become dashes
+pub fn worktree_path(repo: &Repository, branch: &str, template: &str) -> PathBuf {
+    let project = project_path(repo);
+    let repo_name = project
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let repo_name = repo_name.strip_suffix(".git").unwrap_or(&repo_name);
+    let repo_parent = project
+        .parent()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    PathBuf::from(
+        template
+            .replace("{repo_parent}", &repo_parent)
+            .replace("{repo}", repo_name)
+            .replace("{branch}", &branch.replace('/', "-")),
+    )
+}
+
+// name for the administrative directory in `.git/worktrees`, unique within the repository
+fn worktree_name(repo: &Repository, path: &Path) -> String {
+    let base: String = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "worktree".to_string())
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let existing: Vec<String> = repo
+        .worktrees()
+        .map(|names| names.iter().flatten().map(|n| n.to_string()).collect())
+        .unwrap_or_default();
+
+    let mut name = base.clone();
+    let mut counter = 1;
+    while existing.contains(&name) || repo.path().join("worktrees").join(&name).exists() {
+        counter += 1;
+        name = format!("{}-{}", base, counter);
+    }
+    name
+}
+
+pub fn create_worktree(
+    repo_path: &str,
+    branch: &str,
+    target_path: Option<&str>,
+    base_ref: Option<&str>,
+    template: Option<&str>,
+) -> Result<DirectoryResult, String> {
+    let repo = open_main_repository(repo_path)?;
+    if branch.trim().is_empty() {
+        return Err("Branch name is empty".to_string());
+    }
+
+    let target = match target_path.filter(|p| !p.is_empty()) {
+        Some(target) => PathBuf::from(target),
+        None => worktree_path(
+            &repo,
+            branch,
+            template
+                .filter(|t| !t.is_empty())
+                .unwrap_or(DEFAULT_WORKTREE_TEMPLATE),
+        ),
+    };
+    if target.exists() {
+        return Err(format!("'{}' already exists", target.to_string_lossy()));
+    }
+
+    // existing local branch, or a new one from the base ref or a remote branch with the same name
+    let mut created_branch = false;
+    let local_branch = match repo.find_branch(branch, BranchType::Local) {
+        // the base ref only applies to new branches, don't check out something else than asked
+        Ok(_) if base_ref.is_some_and(|b| !b.is_empty()) => {
+            return Err(format!(
+                "Branch {} already exists, leave the base ref empty to check it out",
+                branch
+            ));
+        }
+        Ok(local_branch) => local_branch,
+        Err(_) => {
+            let remote_branch = format!("origin/{}", branch);
+            let (start, track) = match base_ref.filter(|b| !b.is_empty()) {
+                Some(base_ref) => (base_ref.to_string(), false),
+                None if repo.find_branch(&remote_branch, BranchType::Remote).is_ok() => {
+                    (remote_branch, true)
+                }
+                None => (
+                    resolve_base_ref(&repo, None)
+                        .map(|(name, _)| name)
+                        .unwrap_or_else(|| "HEAD".to_string()),
+                    false,
+                ),
+            };
+            let start_commit = repo
+                .revparse_single(&start)
+                .and_then(|o| o.peel_to_commit())
+                .map_err(|e| format!("Could not resolve '{}': {}", start, e.message()))?;
+            let mut new_branch = repo
+                .branch(branch, &start_commit, false)
+                .map_err(|e| format!("Could not create branch {}: {}", branch, e.message()))?;
+            if track {
+                new_branch
+                    .set_upstream(Some(&start))
+                    .map_err(|e| "upstream: ".to_string() + e.message())?;
+            }
+            created_branch = true;
+            new_branch
+        }
+    };
+
+    let reference = local_branch.into_reference();
+    let mut options = WorktreeAddOptions::new();
+    options.reference(Some(&reference));
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Could not create {}: {}", parent.to_string_lossy(), e))?;
+    }
+    let worktree = match repo.worktree(&worktree_name(&repo, &target), &target, Some(&options)) {
+        Ok(worktree) => worktree,
+        Err(e) => {
+            // don't leave a branch behind that the user never got a worktree for
+            if created_branch {
+                if let Ok(mut new_branch) = repo.find_branch(branch, BranchType::Local) {
+                    let _ = new_branch.delete();
+                }
+            }
+            return Err("Could not create worktree: ".to_string() + e.message());
+        }
+    };
+
+    let name = worktree
+        .path()
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut result = DirectoryResult::new(name, worktree.path());
+    result.main_repository = Some(repository_path(&repo).to_string_lossy().to_string());
+    Ok(result)
+}
+
+// removes the `.git/worktrees/*` entries whose working directory is gone, returns their names
+pub fn prune_missing_worktrees(repo: &Repository) -> Result<Vec<String>, String> {
+    let names = repo
+        .worktrees()
+        .map_err(|e| "worktrees: ".to_string() + e.message())?;
+
+    let mut pruned = vec![];
+    for name in names.iter().flatten() {
+        let Ok(worktree) = repo.find_worktree(name) else {
+            continue;
+        };
+        // locked worktrees are not prunable, e.g. ones on a removable drive
+        if worktree.is_prunable(None).unwrap_or(false) {
+            worktree
+                .prune(None)
+                .map_err(|e| format!("Could not prune {}: {}", name, e.message()))?;
+            pruned.push(name.to_string());
+        }
+    }
+    Ok(pruned)
+}
+
+// commits reachable from `head` but from no other branch, tag or remote branch. `own_ref` is
+// left out, stashes and backups don't count as they are only kept around for recovery
+fn unique_commits(repo: &Repository, head: Oid, own_ref: Option<&str>) -> Result<usize, String> {
+    let mut walk = repo
+        .revwalk()
+        .map_err(|e| "revwalk: ".to_string() + e.message())?;
+    walk.push(head)
+        .map_err(|e| "revwalk: ".to_string() + e.message())?;
+    let references = repo
+        .references()
+        .map_err(|e| "references: ".to_string() + e.message())?;
+    for reference in references.flatten() {
+        let name = reference.name().unwrap_or_default();
+        if Some(name) == own_ref
+            || name == "refs/stash"
+            || name.starts_with("refs/worktree-status/")
+        {
+            continue;
+        }
+        if let Ok(commit) = reference.peel_to_commit() {
+            walk.hide(commit.id())
+                .map_err(|e| "revwalk: ".to_string() + e.message())?;
+        }
+    }
+    Ok(walk.count())
+}
+
+// reasons `path` can't be removed without losing work, empty when it is safe
+fn unsafe_to_remove(path: &str, base_ref: Option<&str>) -> Result<Vec<String>, String> {
+    let state = get_branch_state(path, base_ref)?;
+    let mut reasons = vec![];
+
+    if state.state != "clean" {
+        reasons.push(format!("{} in progress", state.state));
+    }
+    // one per file, a file can be both staged and modified
+    let repo = open_worktree(path)?;
+    let changes = repo
+        .statuses(Some(&mut status_options()))
+        .map_err(|e| "statuses: ".to_string() + e.message())?
+        .len();
+    if changes > 0 {
+        reasons.push(format!("{} uncommitted changes", changes));
+    }
+
+    if state.ahead > 0 {
+        reasons.push(format!("{} unpushed commits", state.ahead));
+    }
+    // without an upstream or base branch the commits may only exist on this branch
+    if let Ok(head) = repo.head() {
+        if let Some(target) = head.target() {
+            let own_ref = head.is_branch().then(|| head.name()).flatten();
+            let unique = unique_commits(&repo, target, own_ref)?;
+            if unique > 0 && state.ahead == 0 {
+                reasons.push(format!("{} commits not on any other branch", unique));
+            }
+        }
+    }
+
+    if state.stashes > 0 {
+        reasons.push(format!("{} stashes", state.stashes));
+    }
+    Ok(reasons)
+}
+
+// removes the linked worktree at `path`, refusing when that would lose work, and prunes stale
+// worktree metadata of the repository
+pub fn remove_worktree(
+    path: &str,
+    base_ref: Option<&str>,
+    delete_branch: bool,
+) -> Result<(), String> {
+    let repo = open_repository(path)?;
+    let Some(main_repo) = main_repository(&repo) else {
+        return Err("Only linked worktrees can be removed".to_string());
+    };
+    let worktree = Worktree::open_from_repository(&repo)
+        .map_err(|e| "Could not open worktree: ".to_string() + e.message())?;
+    if let Ok(WorktreeLockStatus::Locked(reason)) = worktree.is_locked() {
+        return Err(format!(
+            "Worktree is locked{}",
+            reason
+                .map(|r| ": ".to_string() + r.trim())
+                .unwrap_or_default()
+        ));
+    }
+
+    let reasons = unsafe_to_remove(path, base_ref)?;
+    if !reasons.is_empty() {
+        return Err(format!("Refusing to remove: {}", reasons.join(", ")));
+    }
+
+    let branch = repo
+        .head()
+        .ok()
+        .filter(|head| head.is_branch())
+        .and_then(|head| head.shorthand().map(|s| s.to_string()));
+    drop(repo);
+
+    let mut options = WorktreePruneOptions::new();
+    options.valid(true).working_tree(true);
+    worktree
+        .prune(Some(&mut options))
+        .map_err(|e| "Could not remove worktree: ".to_string() + e.message())?;
+    // make sure the directory is gone
+    if Path::new(path).exists() {
+        std::fs::remove_dir_all(path).map_err(|e| format!("Could not remove {}: {}", path, e))?;
+    }
+    prune_missing_worktrees(&main_repo)?;
+
+    if let (true, Some(branch)) = (delete_branch, branch) {
+        let mut local_branch = main_repo
+            .find_branch(&branch, BranchType::Local)
+            .map_err(|e| format!("Could not find branch {}: {}", branch, e.message()))?;
+        // like `git branch -d`, only delete a branch whose commits are kept elsewhere
+        if let Some(target) = local_branch.get().target() {
+            let own_ref = local_branch.get().name().map(|n| n.to_string());
+            if unique_commits(&main_repo, target, own_ref.as_deref())? > 0 {
+                return Err(format!("Branch {} is not merged, kept it", branch));
+            }
+        }
+        local_branch
+            .delete()
+            .map_err(|e| format!("Could not delete branch {}: {}", branch, e.message()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_support::*;
+
+    #[test]
+    fn refuses_a_base_ref_for_an_existing_branch() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo_path = dir.path().join("repo");
+        let repo = init_repository(&repo_path);
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.branch("feature", &head, false).unwrap();
+        let target = dir.path().join("feature");
+
+        let result = create_worktree(
+            path_str(&repo_path),
+            "feature",
+            Some(path_str(&target)),
+            Some("HEAD"),
+            None,
+        );
+        assert!(result.is_err());
+        assert!(!target.exists());
+
+        create_worktree(
+            path_str(&repo_path),
+            "feature",
+            Some(path_str(&target)),
+            None,
+            None,
+        )
+        .unwrap();
+        assert!(target.join("README.md").exists());
+    }
+
+    fn linked_worktree(dir: &Path, branch: &str) -> (PathBuf, PathBuf) {
+        let repo_path = dir.join("repo");
+        init_repository(&repo_path);
+        let target = dir.join(branch);
+        create_worktree(
+            path_str(&repo_path),
+            branch,
+            Some(path_str(&target)),
+            None,
+            None,
+        )
+        .unwrap();
+        (repo_path, target)
+    }
+
+    #[test]
+    fn removes_a_worktree_and_its_merged_branch() {
+        let dir = tempfile::tempdir().unwrap();
+        let (repo_path, target) = linked_worktree(dir.path(), "feature");
+
+        remove_worktree(path_str(&target), None, true).unwrap();
+        assert!(!target.exists());
+        let repo = Repository::open(&repo_path).unwrap();
+        assert!(repo.find_branch("feature", BranchType::Local).is_err());
+        assert!(repo.worktrees().unwrap().is_empty());
+    }
+
+    #[test]
+    fn refuses_to_remove_uncommitted_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let (_, target) = linked_worktree(dir.path(), "feature");
+        write_file(&target, "README.md", "changed\n");
+        write_file(&target, "new.txt", "new\n");
+        let worktree = Repository::open(&target).unwrap();
+        // a new staged file counts once, not as staged and added
+        let mut index = worktree.index().unwrap();
+        index.add_path(Path::new("new.txt")).unwrap();
+        index.write().unwrap();
+
+        let error = remove_worktree(path_str(&target), None, true).unwrap_err();
+        assert!(error.contains("2 uncommitted changes"), "{}", error);
+        assert_eq!(read_file(&target, "README.md"), "changed\n");
+    }
+
+    #[test]
+    fn refuses_to_remove_commits_only_on_the_branch() {
+        let dir = tempfile::tempdir().unwrap();
+        let (repo_path, target) = linked_worktree(dir.path(), "feature");
+        let worktree = Repository::open(&target).unwrap();
+        write_file(&target, "new.txt", "new\n");
+        commit_all(&worktree, "only here");
+
+        let error = remove_worktree(path_str(&target), None, true).unwrap_err();
+        assert!(
+            error.contains("1 commits not on any other branch"),
+            "{}",
+            error
+        );
+        assert!(target.exists());
+        let repo = Repository::open(&repo_path).unwrap();
+        assert!(repo.find_branch("feature", BranchType::Local).is_ok());
+    }
+}
@@ -4,21 +4,22 @@ mod windows;
 #[cfg(target_os = "macos")]
 mod macos;
 
-use git2::Repository;
+mod git;
+
+use git::{
+    BranchState, BulkOperation, BulkProgress, BulkReport, CommitInfo, DirectoryResult,
+    FastForwardResult, FetchResult, FileDiff, FileStatus, MoveResult, RepositoryError, StashEntry,
+    UpdateResult,
+};
 use std::{borrow::Cow, sync::Mutex};
 use tauri::{
+    ipc::Channel,
     menu::{Menu, MenuItem},
     tray::TrayIconBuilder,
     Manager, State,
 };
 use tauri_plugin_positioner::{Position, WindowExt};
 
-#[derive(Clone, serde::Serialize, serde::Deserialize)]
-struct DirectoryResult {
-    name: String,
-    path: String,
-}
-
 fn get_app_binary_path(path: &str) -> Result<String, String> {
     #[cfg(target_os = "macos")]
     return macos::get_app_binary_path(path);
@@ -170,155 +171,211 @@ async fn launch_app(
     Ok(())
 }
 
+fn scan_options(
+    filter: Option<String>,
+    discover_worktrees: Option<bool>,
+    max_depth: Option<usize>,
+    skip_directories: Option<Vec<String>>,
+) -> git::ScanOptions {
+    let defaults = git::ScanOptions::default();
+    git::ScanOptions {
+        filter,
+        discover_worktrees: discover_worktrees.unwrap_or(defaults.discover_worktrees),
+        max_depth: max_depth.unwrap_or(defaults.max_depth).max(1),
+        skip_directories: skip_directories.unwrap_or(defaults.skip_directories),
+    }
+}
+
 #[tauri::command]
 async fn scan_directory(
     path: &str,
     filter: Option<String>,
+    discover_worktrees: Option<bool>,
+    max_depth: Option<usize>,
+    skip_directories: Option<Vec<String>>,
 ) -> Result<Vec<DirectoryResult>, String> {
-    let mut paths = vec![];
+    let options = scan_options(filter, discover_worktrees, max_depth, skip_directories);
+    git::scan_directory(path, &options)
+}
 
-    // Turn filter into a regex
-    let re = if let Some(pat) = &filter {
-        Some(fancy_regex::Regex::new(pat).map_err(|e| format!("Invalid regex: {}", e))?)
-    } else {
-        None
-    };
-
-    if let Ok(entries) = std::fs::read_dir(path) {
-        for entry in entries.flatten() {
-            let path = entry.path();
-
-            if path.is_dir() && path.join(".git").exists() {
-                if let Some(r) = &re {
-                    if !r
-                        .is_match(&entry.file_name().to_string_lossy())
-                        .unwrap_or_default()
-                    {
-                        continue;
-                    }
-                }
-                paths.push(DirectoryResult {
-                    name: entry.file_name().to_string_lossy().to_string(),
-                    path: path.to_string_lossy().to_string(),
-                });
-            }
-        }
-    }
-    Ok(paths)
+// runs `operation` over everything scan_directory finds for the same arguments, progress is
+// streamed through `on_progress`
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn bulk_run(
+    path: &str,
+    filter: Option<String>,
+    discover_worktrees: Option<bool>,
+    max_depth: Option<usize>,
+    skip_directories: Option<Vec<String>>,
+    operation: BulkOperation,
+    concurrency: Option<usize>,
+    on_progress: Channel<BulkProgress>,
+) -> Result<BulkReport, String> {
+    let options = scan_options(filter, discover_worktrees, max_depth, skip_directories);
+    let path = path.to_string();
+    // the operations block for as long as the slowest worktree takes, keep them off the async
+    // runtime that serves the other commands
+    tauri::async_runtime::spawn_blocking(move || -> Result<BulkReport, String> {
+        let worktrees = git::scan_directory(&path, &options)?;
+        git::bulk_run(
+            &worktrees,
+            &operation,
+            concurrency.unwrap_or(4),
+            |progress| {
+                let _ = on_progress.send(progress);
+            },
+        )
+    })
+    .await
+    .map_err(|e| "Bulk run failed: ".to_string() + &e.to_string())?
 }
 
-#[derive(Clone, serde::Serialize, serde::Deserialize)]
-struct BranchState {
-    branch: String,
-    ahead: usize,
-    behind: usize,
+#[tauri::command]
+async fn get_branch_state(
+    path: &str,
+    base_ref: Option<String>,
+) -> Result<BranchState, RepositoryError> {
+    git::check_worktree(path)?;
+    git::get_branch_state(path, base_ref.as_deref())
+        .map_err(|message| git::repository_error(path, message))
+}
 
-    staged: usize,
-    modified: usize,
-    deleted: usize,
-    untracked: usize,
-    conflict: usize,
+#[tauri::command]
+async fn fetch_remotes(path: &str) -> Result<Vec<FetchResult>, String> {
+    let path = path.to_string();
+    // network round trips, keep them off the async runtime like bulk_run
+    tauri::async_runtime::spawn_blocking(move || git::fetch_remotes(&path))
+        .await
+        .map_err(|e| "Fetch failed: ".to_string() + &e.to_string())?
 }
 
 #[tauri::command]
-async fn get_branch_state(path: &str) -> Result<BranchState, String> {
-    // use libgit crate to get the branch state
-    let repo = Repository::open(path)
-        .map_err(|e| "Could not open repository: ".to_string() + e.message())?;
-    let head = repo.head();
-
-    if head.is_err() {
-        return Ok(BranchState {
-            branch: "".to_string(),
-            ahead: 0,
-            behind: 0,
-            staged: 0,
-            modified: 0,
-            deleted: 0,
-            untracked: 0,
-            conflict: 0,
-        });
-    }
-    let head = head.unwrap();
-    let branch_name = head.shorthand();
-    if branch_name.is_none() {
-        return Err("Could not get branchname!".to_string());
-    }
+async fn get_file_statuses(path: &str) -> Result<Vec<FileStatus>, String> {
+    git::get_file_statuses(path)
+}
 
-    let mut branch_name = branch_name.unwrap().to_string();
-    if head.is_tag() {
-        let tag = head.peel_to_tag();
-        if let Ok(tag) = tag {
-            branch_name = tag.name().unwrap().to_string();
-        }
-        branch_name += " is tag!";
-    }
+#[tauri::command]
+async fn get_file_diff(path: &str, file: &str, staged: bool) -> Result<FileDiff, String> {
+    git::get_file_diff(path, file, staged)
+}
 
-    let branch_name = branch_name;
-    let mut ahead = 0;
-    let mut behind = 0;
-
-    // get git branch
-    if head.is_branch() {
-        let branch_name = head.shorthand().unwrap().to_string();
-        let branch = repo
-            .find_branch(&branch_name, git2::BranchType::Local)
-            .map_err(|e| e.message().to_string() + "branch!" + &branch_name)?;
-        if let Ok(upstream) = branch.upstream() {
-            (ahead, behind) = repo
-                .graph_ahead_behind(
-                    branch.get().target().unwrap(),
-                    upstream.get().target().unwrap(),
-                )
-                .map_err(|e| e.message().to_string() + "ahead-behind!")?;
-        }
-    }
-    let (ahead, behind) = (ahead, behind);
-
-    let statuses = repo
-        .statuses(None)
-        .map_err(|e| e.message().to_string() + "statuses")?;
-
-    let mut staged = 0;
-    let mut modified = 0;
-    let mut deleted = 0;
-    let mut untracked = 0;
-    let mut conflict = 0;
-
-    for entry in statuses.iter() {
-        let status = entry.status();
-        if status.is_index_new()
-            || status.is_index_modified()
-            || status.is_index_deleted()
-            || status.is_index_renamed()
-            || status.is_index_typechange()
-        {
-            staged += 1;
-        }
-        if status.is_wt_new() {
-            untracked += 1;
-        }
-        if status.is_wt_modified() {
-            modified += 1;
-        }
-        if status.is_wt_deleted() {
-            deleted += 1;
-        }
-        if status.is_conflicted() {
-            conflict += 1;
-        }
-    }
+#[tauri::command]
+async fn stage_files(path: &str, files: Vec<String>) -> Result<(), String> {
+    git::stage_files(path, files)
+}
 
-    Ok(BranchState {
-        branch: branch_name,
-        ahead,
-        behind,
-        staged,
-        modified,
-        deleted,
-        untracked,
-        conflict,
-    })
+#[tauri::command]
+async fn unstage_files(path: &str, files: Vec<String>) -> Result<(), String> {
+    git::unstage_files(path, files)
+}
+
+#[tauri::command]
+async fn stage_hunk(path: &str, file: &str, hunk: usize) -> Result<(), String> {
+    git::stage_hunk(path, file, hunk)
+}
+
+#[tauri::command]
+async fn unstage_hunk(path: &str, file: &str, hunk: usize) -> Result<(), String> {
+    git::unstage_hunk(path, file, hunk)
+}
+
+#[tauri::command]
+async fn discard_token(path: &str, files: Vec<String>) -> Result<String, String> {
+    git::discard_token(path, &files)
+}
+
+#[tauri::command]
+async fn discard_changes(
+    path: &str,
+    files: Vec<String>,
+    token: &str,
+) -> Result<Option<String>, String> {
+    git::discard_changes(path, files, token)
+}
+
+#[tauri::command]
+async fn commit(path: &str, message: &str, amend: Option<bool>) -> Result<CommitInfo, String> {
+    git::commit(path, message, amend.unwrap_or(false))
+}
+
+#[tauri::command]
+async fn create_worktree(
+    path: &str,
+    branch: &str,
+    target_path: Option<String>,
+    base_ref: Option<String>,
+    template: Option<String>,
+) -> Result<DirectoryResult, String> {
+    git::create_worktree(
+        path,
+        branch,
+        target_path.as_deref(),
+        base_ref.as_deref(),
+        template.as_deref(),
+    )
+}
+
+#[tauri::command]
+async fn remove_worktree(
+    path: &str,
+    base_ref: Option<String>,
+    delete_branch: Option<bool>,
+) -> Result<(), String> {
+    git::remove_worktree(path, base_ref.as_deref(), delete_branch.unwrap_or(false))
+}
+
+#[tauri::command]
+async fn repair_worktree(path: &str, main_repository: Option<String>) -> Result<(), String> {
+    git::repair_worktree(path, main_repository.as_deref())
+}
+
+#[tauri::command]
+async fn prune_worktrees(path: &str) -> Result<Vec<String>, String> {
+    git::prune_worktrees(path)
+}
+
+#[tauri::command]
+async fn checkout_branch(
+    path: &str,
+    branch: &str,
+    carry_changes: Option<bool>,
+) -> Result<(), String> {
+    git::checkout_branch(path, branch, carry_changes.unwrap_or(false))
+}
+
+#[tauri::command]
+async fn recent_branches(path: &str) -> Result<Vec<String>, String> {
+    git::recent_branches(path)
+}
+
+#[tauri::command]
+async fn fast_forward(path: &str) -> Result<FastForwardResult, String> {
+    Ok(git::fast_forward(path))
+}
+
+#[tauri::command]
+async fn update_worktrees(
+    path: &str,
+    base_ref: Option<String>,
+    merge: Option<bool>,
+) -> Result<Vec<UpdateResult>, String> {
+    git::update_worktrees(path, base_ref.as_deref(), merge.unwrap_or(false))
+}
+
+#[tauri::command]
+async fn predict_conflicts(path: &str, base_ref: Option<String>) -> Result<Vec<String>, String> {
+    git::predict_conflicts(path, base_ref.as_deref())
+}
+
+#[tauri::command]
+async fn move_changes(source: &str, target: &str) -> Result<MoveResult, String> {
+    git::move_changes(source, target)
+}
+
+#[tauri::command]
+async fn list_stashes(path: &str) -> Result<Vec<StashEntry>, String> {
+    git::list_stashes(path)
 }
 
 fn setup_menu(app: &tauri::App, tray: &tauri::tray::TrayIcon) -> tauri::Result<()> {
@@ -409,6 +466,7 @@ fn serve_image(req: tauri::http::Request<Vec<u8>>) -> Result<(&'static str, Vec<
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    git::set_network_timeouts();
     let tauri_builder = tauri::Builder::default();
     #[cfg(not(debug_assertions))]
     let tauri_builder = tauri_builder.plugin(tauri_plugin_updater::Builder::new().build());
@@ -510,6 +568,28 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             scan_directory,
             get_branch_state,
+            get_file_statuses,
+            get_file_diff,
+            stage_files,
+            unstage_files,
+            stage_hunk,
+            unstage_hunk,
+            discard_token,
+            discard_changes,
+            commit,
+            create_worktree,
+            remove_worktree,
+            repair_worktree,
+            prune_worktrees,
+            checkout_branch,
+            recent_branches,
+            fast_forward,
+            bulk_run,
+            update_worktrees,
+            predict_conflicts,
+            move_changes,
+            list_stashes,
+            fetch_remotes,
             launch_app,
             hide_on_focus_lost,
             get_default_actions
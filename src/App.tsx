@@ -30,6 +30,9 @@ import { WorktreeStatusContext } from "./contexts/WorktreeStatusContext";
 // Actions
 import { scan_directory } from "./actions/scan_directory";
 import { get_branch_state } from "./actions/get_branch_state";
+import { fetch_remotes } from "./actions/fetch_remotes";
+import { get_file_statuses } from "./actions/get_file_statuses";
+import { get_file_diff } from "./actions/get_file_diff";
 import { launch_app } from "./actions/launch_app";
 import { hide_on_focus_lost } from "./actions/hide_on_focus_lost";
 import { get_default_actions } from "./actions/get_default_actions";
@@ -79,7 +82,7 @@ function App() {
           c.paths = c.paths.map((p) => {
             if (typeof p === "string")
               // Convert string paths to WorktreePath objects
-              return { key: crypto.randomUUID(), path: p, displayName: null, filter: null, defaultCollapse: false };
+              return { key: crypto.randomUUID(), path: p, displayName: null, filter: null, defaultCollapse: false, discoverWorktrees: false, maxDepth: null, skipDirectories: null, baseRef: null, fetchInterval: null, worktreeTemplate: null, updateWithMerge: false };
             return p;
           });
         }
@@ -107,7 +110,7 @@ function App() {
     });
     await hide_on_focus_lost(true);
     if (selected !== null)
-      setConfig({ ...config, paths: [...config.paths, { key: crypto.randomUUID(), path: selected, displayName: null, filter: null, defaultCollapse: false }] });
+      setConfig({ ...config, paths: [...config.paths, { key: crypto.randomUUID(), path: selected, displayName: null, filter: null, defaultCollapse: false, discoverWorktrees: false, maxDepth: null, skipDirectories: null, baseRef: null, fetchInterval: null, worktreeTemplate: null, updateWithMerge: false }] });
   }
   function removePath(key: string) {
     setConfig({ ...config, paths: config.paths.filter((v) => v.key !== key) });
@@ -220,6 +223,9 @@ function App() {
             seed: seed,
             scan_directory: scan_directory,
             get_branch_state: get_branch_state,
+            fetch_remotes: fetch_remotes,
+            get_file_statuses: get_file_statuses,
+            get_file_diff: get_file_diff,
           }}
         >
           <ActionContext.Provider
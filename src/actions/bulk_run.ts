@@ -0,0 +1,22 @@
+import { invoke, Channel } from "@tauri-apps/api/core";
+import { BulkOperation, BulkProgress, BulkReport, WorktreePath } from "../types";
+
+export async function bulk_run(
+  worktreePath: WorktreePath,
+  operation: BulkOperation,
+  concurrency: number | null,
+  onProgress: (progress: BulkProgress) => void
+) {
+  const channel = new Channel<BulkProgress>();
+  channel.onmessage = onProgress;
+  return (await invoke("bulk_run", {
+    path: worktreePath.path,
+    filter: worktreePath.filter,
+    discoverWorktrees: worktreePath.discoverWorktrees ?? false,
+    maxDepth: worktreePath.maxDepth ?? null,
+    skipDirectories: worktreePath.skipDirectories ?? null,
+    operation,
+    concurrency,
+    onProgress: channel,
+  })) as BulkReport;
+}
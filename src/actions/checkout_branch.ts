@@ -0,0 +1,9 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export async function checkout_branch(
+  path: string,
+  branch: string,
+  carryChanges: boolean
+) {
+  await invoke("checkout_branch", { path, branch, carryChanges });
+}
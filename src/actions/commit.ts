@@ -0,0 +1,6 @@
+import { invoke } from "@tauri-apps/api/core";
+import { CommitInfo } from "../types";
+
+export async function commit(path: string, message: string, amend: boolean) {
+  return (await invoke("commit", { path, message, amend })) as CommitInfo;
+}
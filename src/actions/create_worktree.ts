@@ -0,0 +1,18 @@
+import { invoke } from "@tauri-apps/api/core";
+import { DirectoryResult } from "../types";
+
+export async function create_worktree(
+  path: string,
+  branch: string,
+  targetPath: string | null,
+  baseRef: string | null,
+  template: string | null
+) {
+  return (await invoke("create_worktree", {
+    path,
+    branch,
+    targetPath,
+    baseRef,
+    template,
+  })) as DirectoryResult;
+}
@@ -0,0 +1,12 @@
+import { invoke } from "@tauri-apps/api/core";
+
+// returns the backup ref the discarded changes were saved to
+export async function discard_changes(
+  path: string,
+  files: string[],
+  token: string
+) {
+  return (await invoke("discard_changes", { path, files, token })) as
+    | string
+    | null;
+}
@@ -0,0 +1,5 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export async function discard_token(path: string, files: string[]) {
+  return (await invoke("discard_token", { path, files })) as string;
+}
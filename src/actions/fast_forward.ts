@@ -0,0 +1,6 @@
+import { invoke } from "@tauri-apps/api/core";
+import { FastForwardResult } from "../types";
+
+export async function fast_forward(path: string) {
+  return (await invoke("fast_forward", { path })) as FastForwardResult;
+}
@@ -0,0 +1,6 @@
+import { invoke } from "@tauri-apps/api/core";
+import { FetchResult } from "../types";
+
+export async function fetch_remotes(path: string) {
+  return (await invoke("fetch_remotes", { path })) as FetchResult[];
+}
@@ -1,6 +1,6 @@
 import { invoke } from "@tauri-apps/api/core";
 import { BranchState } from "../types";
 
-export async function get_branch_state(path: string) {
-  return (await invoke("get_branch_state", { path })) as BranchState;
+export async function get_branch_state(path: string, baseRef: string | null) {
+  return (await invoke("get_branch_state", { path, baseRef })) as BranchState;
 }
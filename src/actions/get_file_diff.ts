@@ -0,0 +1,6 @@
+import { invoke } from "@tauri-apps/api/core";
+import { FileDiff } from "../types";
+
+export async function get_file_diff(path: string, file: string, staged: boolean) {
+  return (await invoke("get_file_diff", { path, file, staged })) as FileDiff;
+}
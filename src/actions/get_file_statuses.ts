@@ -0,0 +1,6 @@
+import { invoke } from "@tauri-apps/api/core";
+import { FileStatus } from "../types";
+
+export async function get_file_statuses(path: string) {
+  return (await invoke("get_file_statuses", { path })) as FileStatus[];
+}
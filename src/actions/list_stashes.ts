@@ -0,0 +1,6 @@
+import { invoke } from "@tauri-apps/api/core";
+import { StashEntry } from "../types";
+
+export async function list_stashes(path: string) {
+  return (await invoke("list_stashes", { path })) as StashEntry[];
+}
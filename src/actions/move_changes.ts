@@ -0,0 +1,6 @@
+import { invoke } from "@tauri-apps/api/core";
+import { MoveResult } from "../types";
+
+export async function move_changes(source: string, target: string) {
+  return (await invoke("move_changes", { source, target })) as MoveResult;
+}
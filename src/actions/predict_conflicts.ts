@@ -0,0 +1,5 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export async function predict_conflicts(path: string, baseRef: string | null) {
+  return (await invoke("predict_conflicts", { path, baseRef })) as string[];
+}
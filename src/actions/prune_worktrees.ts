@@ -0,0 +1,5 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export async function prune_worktrees(path: string) {
+  return (await invoke("prune_worktrees", { path })) as string[];
+}
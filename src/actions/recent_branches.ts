@@ -0,0 +1,5 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export async function recent_branches(path: string) {
+  return (await invoke("recent_branches", { path })) as string[];
+}
@@ -0,0 +1,9 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export async function remove_worktree(
+  path: string,
+  baseRef: string | null,
+  deleteBranch: boolean
+) {
+  await invoke("remove_worktree", { path, baseRef, deleteBranch });
+}
@@ -0,0 +1,8 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export async function repair_worktree(
+  path: string,
+  mainRepository: string | null
+) {
+  await invoke("repair_worktree", { path, mainRepository });
+}
@@ -1,9 +1,18 @@
 import { invoke } from "@tauri-apps/api/core";
 import { DirectoryResult } from "../types";
 
-export async function scan_directory(basepath: string, filter: string | null) {
+export async function scan_directory(
+  basepath: string,
+  filter: string | null,
+  discoverWorktrees: boolean,
+  maxDepth: number | null,
+  skipDirectories: string[] | null
+) {
   return (await invoke("scan_directory", {
     path: basepath,
     filter: filter,
+    discoverWorktrees: discoverWorktrees,
+    maxDepth: maxDepth,
+    skipDirectories: skipDirectories,
   })) as DirectoryResult[];
 }
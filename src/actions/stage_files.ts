@@ -0,0 +1,5 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export async function stage_files(path: string, files: string[]) {
+  await invoke("stage_files", { path, files });
+}
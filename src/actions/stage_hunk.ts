@@ -0,0 +1,5 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export async function stage_hunk(path: string, file: string, hunk: number) {
+  await invoke("stage_hunk", { path, file, hunk });
+}
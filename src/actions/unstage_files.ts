@@ -0,0 +1,5 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export async function unstage_files(path: string, files: string[]) {
+  await invoke("unstage_files", { path, files });
+}
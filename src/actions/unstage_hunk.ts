@@ -0,0 +1,5 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export async function unstage_hunk(path: string, file: string, hunk: number) {
+  await invoke("unstage_hunk", { path, file, hunk });
+}
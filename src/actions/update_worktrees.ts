@@ -0,0 +1,14 @@
+import { invoke } from "@tauri-apps/api/core";
+import { UpdateResult } from "../types";
+
+export async function update_worktrees(
+  path: string,
+  baseRef: string | null,
+  merge: boolean
+) {
+  return (await invoke("update_worktrees", {
+    path,
+    baseRef,
+    merge,
+  })) as UpdateResult[];
+}
@@ -0,0 +1,96 @@
+import React from "react";
+import Form from "react-bootstrap/Form";
+import Button from "react-bootstrap/Button";
+
+import { checkout_branch } from "../actions/checkout_branch";
+import { recent_branches } from "../actions/recent_branches";
+
+interface BranchSwitcherProps {
+  path: string;
+  changeSeed: number;
+  onSwitch: () => void;
+}
+
+export const BranchSwitcher: React.FC<BranchSwitcherProps> = ({
+  path,
+  changeSeed,
+  onSwitch,
+}) => {
+  const [branch, setBranch] = React.useState("");
+  const [carry, setCarry] = React.useState(false);
+  const [recent, setRecent] = React.useState<string[]>([]);
+  const [error, setError] = React.useState<string | null>(null);
+
+  React.useEffect(() => {
+    var setState = true;
+    recent_branches(path)
+      .then((branches) => {
+        if (setState) setRecent(branches);
+      })
+      .catch((oeps: string) => console.error(oeps));
+    return () => {
+      setState = false;
+    };
+  }, [path, changeSeed]);
+
+  async function submit() {
+    setError(null);
+    const switched = await checkout_branch(path, branch.trim(), carry)
+      .then(() => true)
+      .catch((oeps: string) => {
+        setError(oeps);
+        return false;
+      });
+    if (switched) {
+      setBranch("");
+      setCarry(false);
+      onSwitch();
+    }
+  }
+
+  return (
+    <Form
+      className="branch-switcher mt-1"
+      onSubmit={(e) => {
+        e.preventDefault();
+        submit();
+      }}
+    >
+      <div className="d-flex gap-1">
+        <Form.Control
+          size="sm"
+          type="text"
+          placeholder="Switch to branch"
+          list={`recent-branches-${path}`}
+          value={branch}
+          onChange={(e) => setBranch(e.target.value)}
+        />
+        <datalist id={`recent-branches-${path}`}>
+          {recent.map((b) => (
+            <option key={b} value={b} />
+          ))}
+        </datalist>
+        <Form.Check
+          inline
+          type="checkbox"
+          id={`carry-${path}`}
+          label="Carry changes"
+          title="Take uncommitted changes along to the other branch"
+          checked={carry}
+          onChange={(e) => setCarry(e.target.checked)}
+        />
+        <Button
+          size="sm"
+          variant="secondary"
+          type="submit"
+          disabled={branch.trim() === ""}
+        >
+          Switch
+        </Button>
+      </div>
+      {error && <div className="text-danger">{error}</div>}
+    </Form>
+  );
+};
+
+export default BranchSwitcher;
@@ -0,0 +1,121 @@
+import React from "react";
+import Form from "react-bootstrap/Form";
+import Button from "react-bootstrap/Button";
+import ProgressBar from "react-bootstrap/ProgressBar";
+
+import { bulk_run } from "../actions/bulk_run";
+import { BulkOperation, BulkProgress, BulkReport, WorktreePath } from "../types";
+
+interface BulkRunProps {
+  worktreePath: WorktreePath;
+  onFinished: () => void;
+}
+
+export const BulkRun: React.FC<BulkRunProps> = ({ worktreePath, onFinished }) => {
+  const [kind, setKind] = React.useState<BulkOperation["kind"]>("fetch");
+  const [command, setCommand] = React.useState("");
+  const [progress, setProgress] = React.useState<BulkProgress | null>(null);
+  const [running, setRunning] = React.useState<string[]>([]);
+  const [report, setReport] = React.useState<BulkReport | null>(null);
+  const [error, setError] = React.useState<string | null>(null);
+
+  function operation(): BulkOperation {
+    if (kind !== "custom") return { kind };
+    return { kind, command };
+  }
+
+  async function run() {
+    setError(null);
+    setReport(null);
+    setRunning([]);
+    const result = await bulk_run(worktreePath, operation(), null, (event) => {
+      setProgress(event);
+      setRunning((running) =>
+        event.event === "started"
+          ? [...running, event.path]
+          : running.filter((path) => path !== event.path)
+      );
+    }).catch((oeps: string) => {
+      setError(oeps);
+      return null;
+    });
+    setProgress(null);
+    setReport(result);
+    onFinished();
+  }
+
+  const failed = report?.results.filter(
+    (result) => !["ok", "updated", "up-to-date"].includes(result.status)
+  );
+
+  return (
+    <Form
+      className="bulk-run mb-1"
+      onSubmit={(e) => {
+        e.preventDefault();
+        run();
+      }}
+    >
+      <div className="d-flex gap-1">
+        <Form.Select
+          size="sm"
+          value={kind}
+          onChange={(e) => setKind(e.target.value as BulkOperation["kind"])}
+        >
+          <option value="fetch">Fetch</option>
+          <option value="fast-forward">Fast-forward</option>
+          <option value="prune">Prune worktrees</option>
+          <option value="gc">Garbage collect</option>
+          <option value="custom">Run command</option>
+        </Form.Select>
+        {kind === "custom" && (
+          <Form.Control
+            size="sm"
+            type="text"
+            placeholder="Command, {folder} is the worktree path"
+            value={command}
+            onChange={(e) => setCommand(e.target.value)}
+          />
+        )}
+        <Button
+          size="sm"
+          variant="outline-primary"
+          type="submit"
+          disabled={progress !== null || (kind === "custom" && command.trim() === "")}
+        >
+          Run on all
+        </Button>
+      </div>
+      {progress !== null && (
+        <ProgressBar
+          className="mt-1"
+          now={progress.completed}
+          max={progress.total}
+          label={`${progress.completed}/${progress.total}`}
+          title={running.join("\n")}
+        />
+      )}
+      {report !== null && (
+        <div className="small">
+          {report.operation}:{" "}
+          {Object.entries(report.counts)
+            .map(([status, count]) => `${count} ${status}`)
+            .join(", ")}
+          {failed && failed.length > 0 && (
+            <ul className="list-unstyled mb-0">
+              {failed.map((result) => (
+                <li key={result.path} className="text-warning">
+                  {result.path}: {result.status}
+                  {result.message && ` (${result.message})`}
+                </li>
+              ))}
+            </ul>
+          )}
+        </div>
+      )}
+      {error && <div className="text-danger">{error}</div>}
+    </Form>
+  );
+};
+
+export default BulkRun;
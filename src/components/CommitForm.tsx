@@ -0,0 +1,69 @@
+import React from "react";
+import Form from "react-bootstrap/Form";
+import Button from "react-bootstrap/Button";
+
+import { commit } from "../actions/commit";
+
+interface CommitFormProps {
+  path: string;
+  onCommit: () => void;
+}
+
+export const CommitForm: React.FC<CommitFormProps> = ({ path, onCommit }) => {
+  const [message, setMessage] = React.useState("");
+  const [amend, setAmend] = React.useState(false);
+  const [error, setError] = React.useState<string | null>(null);
+
+  async function submit() {
+    setError(null);
+    const result = await commit(path, message, amend).catch((oeps: string) => {
+      setError(oeps);
+      return null;
+    });
+    if (result !== null) {
+      setMessage("");
+      setAmend(false);
+      onCommit();
+    }
+  }
+
+  return (
+    <Form
+      className="commit-form mt-1"
+      onSubmit={(e) => {
+        e.preventDefault();
+        submit();
+      }}
+    >
+      <Form.Control
+        size="sm"
+        type="text"
+        placeholder="Commit message"
+        value={message}
+        onChange={(e) => setMessage(e.target.value)}
+      />
+      <div className="clearfix mt-1">
+        <Form.Check
+          inline
+          type="checkbox"
+          id={`amend-${path}`}
+          label="Amend"
+          checked={amend}
+          onChange={(e) => setAmend(e.target.checked)}
+        />
+        <Button
+          className="float-end"
+          size="sm"
+          variant="primary"
+          type="submit"
+          disabled={message.trim() === ""}
+        >
+          Commit
+        </Button>
+      </div>
+      {error && <div className="text-danger">{error}</div>}
+    </Form>
+  );
+};
+
+export default CommitForm;
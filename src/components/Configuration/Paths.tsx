@@ -42,6 +42,48 @@ const WorktreePathConfig: React.FC<{
           type: "bool",
           value: path.defaultCollapse ? "true" : "false",
         },
+        {
+          key: `path-${path.key}-discoverWorktrees`,
+          displayName: "Discover Worktrees",
+          type: "bool",
+          value: path.discoverWorktrees ? "true" : "false",
+        },
+        {
+          key: `path-${path.key}-maxDepth`,
+          displayName: "Max Depth",
+          type: "number",
+          value: path.maxDepth?.toString() || "",
+        },
+        {
+          key: `path-${path.key}-skipDirectories`,
+          displayName: "Skip Directories (comma separated)",
+          type: "string",
+          value: path.skipDirectories?.join(", ") || "",
+        },
+        {
+          key: `path-${path.key}-baseRef`,
+          displayName: "Base Branch (default: origin/HEAD)",
+          type: "string",
+          value: path.baseRef || "",
+        },
+        {
+          key: `path-${path.key}-fetchInterval`,
+          displayName: "Fetch Interval (minutes, empty to disable)",
+          type: "number",
+          value: path.fetchInterval?.toString() || "",
+        },
+        {
+          key: `path-${path.key}-worktreeTemplate`,
+          displayName: "New Worktree Path (default: {repo_parent}/{repo}-{branch})",
+          type: "string",
+          value: path.worktreeTemplate || "",
+        },
+        {
+          key: `path-${path.key}-updateWithMerge`,
+          displayName: "Merge the base branch into worktrees instead of rebasing",
+          type: "bool",
+          value: path.updateWithMerge ? "true" : "false",
+        },
       ]);
     }, [path]
   );
@@ -85,6 +127,14 @@ const WorktreePathConfig: React.FC<{
                   displayName: settings.find(s => s.key === `path-${path.key}-displayName`)?.value || null,
                   filter: settings.find(s => s.key === `path-${path.key}-filter`)?.value || null,
                   defaultCollapse: settings.find(s => s.key === `path-${path.key}-defaultCollapse`)?.value === "true",
+                  discoverWorktrees: settings.find(s => s.key === `path-${path.key}-discoverWorktrees`)?.value === "true",
+                  maxDepth: parseInt(settings.find(s => s.key === `path-${path.key}-maxDepth`)?.value || "") || null,
+                  skipDirectories: settings.find(s => s.key === `path-${path.key}-skipDirectories`)?.value
+                    ?.split(",").map(s => s.trim()).filter(s => s.length > 0) || null,
+                  baseRef: settings.find(s => s.key === `path-${path.key}-baseRef`)?.value || null,
+                  fetchInterval: parseInt(settings.find(s => s.key === `path-${path.key}-fetchInterval`)?.value || "") || null,
+                  worktreeTemplate: settings.find(s => s.key === `path-${path.key}-worktreeTemplate`)?.value || null,
+                  updateWithMerge: settings.find(s => s.key === `path-${path.key}-updateWithMerge`)?.value === "true",
                 })
               }}
             >
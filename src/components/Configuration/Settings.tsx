@@ -12,11 +12,11 @@ export const Settings: React.FC<SettingsProps> = ({ settings, onUpdateSettings }
         <div>
             <Form>
                 {settings.map((setting, index) => {
-                    if (setting.type === "string") {
+                    if (setting.type === "string" || setting.type === "number") {
                         return <Form.Group key={setting.key} className="mb-3">
                             <Form.Label>{setting.displayName}</Form.Label>
                             <Form.Control
-                                type="text"
+                                type={setting.type === "number" ? "number" : "text"}
                                 defaultValue={setting.value}
                                 onChange={(e) => {
                                     const newSettings = [...settings];
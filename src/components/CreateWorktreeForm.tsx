@@ -0,0 +1,81 @@
+import React from "react";
+import Form from "react-bootstrap/Form";
+import Button from "react-bootstrap/Button";
+
+import { create_worktree } from "../actions/create_worktree";
+
+interface CreateWorktreeFormProps {
+  path: string;
+  baseRef: string | null;
+  template: string | null;
+  onCreate: () => void;
+}
+
+export const CreateWorktreeForm: React.FC<CreateWorktreeFormProps> = ({
+  path,
+  baseRef,
+  template,
+  onCreate,
+}) => {
+  const [branch, setBranch] = React.useState("");
+  const [startRef, setStartRef] = React.useState(baseRef ?? "");
+  const [error, setError] = React.useState<string | null>(null);
+
+  async function submit() {
+    setError(null);
+    const result = await create_worktree(
+      path,
+      branch.trim(),
+      null,
+      startRef.trim() || null,
+      template
+    ).catch((oeps: string) => {
+      setError(oeps);
+      return null;
+    });
+    if (result !== null) {
+      setBranch("");
+      onCreate();
+    }
+  }
+
+  return (
+    <Form
+      className="create-worktree-form mt-1"
+      onSubmit={(e) => {
+        e.preventDefault();
+        submit();
+      }}
+    >
+      <div className="d-flex gap-1">
+        <Form.Control
+          size="sm"
+          type="text"
+          placeholder="New worktree branch"
+          title="An existing branch is checked out, a new one is created from the base ref"
+          value={branch}
+          onChange={(e) => setBranch(e.target.value)}
+        />
+        <Form.Control
+          size="sm"
+          type="text"
+          placeholder="Base ref"
+          title="Start of a new branch, leave empty to check out an existing branch"
+          value={startRef}
+          onChange={(e) => setStartRef(e.target.value)}
+        />
+        <Button
+          size="sm"
+          variant="secondary"
+          type="submit"
+          disabled={branch.trim() === ""}
+        >
+          Add
+        </Button>
+      </div>
+      {error && <div className="text-danger">{error}</div>}
+    </Form>
+  );
+};
+
+export default CreateWorktreeForm;
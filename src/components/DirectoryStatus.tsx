@@ -3,6 +3,7 @@ import { Worktree } from "./Worktree";
 
 import { DirectoryResult, WorktreePath } from "../types";
 import { WorktreeStatusContext } from "../contexts/WorktreeStatusContext";
+import { BulkRun } from "./BulkRun";
 interface DirectoryStatusProps {
   worktreePath: WorktreePath;
 }
@@ -11,23 +12,96 @@ export const DirectoryStatus: React.FC<DirectoryStatusProps> = ({
   worktreePath,
 }) => {
   const [worktrees, setWorktrees] = React.useState<DirectoryResult[]>([]);
+  const [fetchSeed, setFetchSeed] = React.useState(0);
+  const [scanSeed, setScanSeed] = React.useState(0);
 
   const worktreeContext = React.useContext(WorktreeStatusContext);
   React.useEffect(() => {
     let stillActive = true;
-    async function scan_directory(basepath: string, filter: string | null) {
-      const result = await worktreeContext.scan_directory(basepath, filter);
+    async function scan_directory(
+      basepath: string,
+      filter: string | null,
+      discoverWorktrees: boolean,
+      maxDepth: number | null,
+      skipDirectories: string[] | null
+    ) {
+      const result = await worktreeContext.scan_directory(
+        basepath,
+        filter,
+        discoverWorktrees,
+        maxDepth,
+        skipDirectories
+      );
       if (stillActive) setWorktrees(result);
     }
-    scan_directory(worktreePath.path, worktreePath.filter);
+    scan_directory(
+      worktreePath.path,
+      worktreePath.filter,
+      worktreePath.discoverWorktrees ?? false,
+      worktreePath.maxDepth ?? null,
+      worktreePath.skipDirectories ?? null
+    );
     return () => {
       stillActive = false;
     };
-  }, [worktreePath]);
+  }, [worktreePath, scanSeed]);
+
+  React.useEffect(() => {
+    if (!worktreePath.fetchInterval) return;
+    const interval = setInterval(async () => {
+      // worktrees of one repository share their remotes, fetch each repository once
+      const repositories = new Set(
+        worktrees.map((worktree) => worktree.main_repository ?? worktree.path)
+      );
+      for (const repository of repositories) {
+        await worktreeContext
+          .fetch_remotes(repository)
+          .then((results) =>
+            results
+              .filter((result) => result.error)
+              .forEach((result) => console.error(result.error))
+          )
+          .catch((oeps: string) => console.error(oeps));
+      }
+      setFetchSeed((seed) => seed + 1);
+    }, worktreePath.fetchInterval * 60 * 1000);
+    return () => {
+      clearInterval(interval);
+    };
+  }, [worktreePath.fetchInterval, worktrees]);
+
   return (
     <div>
+      <BulkRun
+        worktreePath={worktreePath}
+        onFinished={() => {
+          // prune can drop worktrees from the list
+          setScanSeed(scanSeed + 1);
+          setFetchSeed(fetchSeed + 1);
+        }}
+      />
       {worktrees.map((worktree, index) => (
-        <Worktree key={worktreePath.key + "-" + index} {...worktree} />
+        <Worktree
+          key={worktreePath.key + "-" + index}
+          {...worktree}
+          baseRef={worktreePath.baseRef ?? null}
+          fetchSeed={fetchSeed}
+          worktreeTemplate={worktreePath.worktreeTemplate ?? null}
+          updateWithMerge={worktreePath.updateWithMerge ?? false}
+          siblings={worktrees
+            .filter(
+              (other) =>
+                other.path !== worktree.path &&
+                !other.bare &&
+                (other.main_repository ?? other.path) ===
+                  (worktree.main_repository ?? worktree.path)
+            )
+            .map((other) => other.path)}
+          onWorktreesChanged={() => {
+            setScanSeed(scanSeed + 1);
+            setFetchSeed(fetchSeed + 1);
+          }}
+        />
       ))}
     </div>
   );
@@ -0,0 +1,85 @@
+import React from "react";
+
+import { WorktreeStatusContext } from "../contexts/WorktreeStatusContext";
+import { FileDiff } from "../types";
+import { stage_hunk } from "../actions/stage_hunk";
+import { unstage_hunk } from "../actions/unstage_hunk";
+
+interface FileDiffViewProps {
+  path: string;
+  file: string;
+  staged: boolean;
+  onChange: () => void;
+}
+
+export const FileDiffView: React.FC<FileDiffViewProps> = ({
+  path,
+  file,
+  staged,
+  onChange,
+}) => {
+  const [diff, setDiff] = React.useState<FileDiff | null>(null);
+  const [error, setError] = React.useState<string | null>(null);
+
+  const worktreeContext = React.useContext(WorktreeStatusContext);
+
+  React.useEffect(() => {
+    let stillActive = true;
+    worktreeContext
+      .get_file_diff(path, file, staged)
+      .then((result) => {
+        if (stillActive) setDiff(result);
+      })
+      .catch((oeps: string) => setError(oeps));
+    return () => {
+      stillActive = false;
+    };
+  }, [path, file, staged, worktreeContext.seed]);
+
+  if (error) return <div className="text-danger">{error}</div>;
+  if (diff === null) return <div>Loading...</div>;
+  if (diff.binary) return <div className="text-muted">Binary file</div>;
+  return (
+    <div className="diff-view">
+      {diff.hunks.map((hunk, hunkIndex) => (
+        <div key={hunk.header}>
+          <div className="text-info">
+            {hunk.header}
+            <span
+              className="float-end"
+              style={{ cursor: "pointer" }}
+              title={staged ? "unstage hunk" : "stage hunk"}
+              onClick={() =>
+                (staged ? unstage_hunk : stage_hunk)(path, file, hunkIndex)
+                  .catch((oeps: string) => setError(oeps))
+                  .then(onChange)
+              }
+            >
+              {staged ? "−" : "+"}
+            </span>
+          </div>
+          {hunk.lines.map((line, index) => (
+            <div
+              key={index}
+              className={
+                line.origin === "+"
+                  ? "text-success"
+                  : line.origin === "-"
+                  ? "text-danger"
+                  : ""
+              }
+            >
+              <span className="text-muted">
+                {(line.new_lineno ?? line.old_lineno)?.toString().padStart(4)}
+              </span>{" "}
+              {line.origin}
+              {line.content.trimEnd()}
+            </div>
+          ))}
+        </div>
+      ))}
+    </div>
+  );
+};
+
+export default FileDiffView;
@@ -0,0 +1,154 @@
+import React from "react";
+import { confirm } from "@tauri-apps/plugin-dialog";
+
+import { WorktreeStatusContext } from "../contexts/WorktreeStatusContext";
+import { FileStatus } from "../types";
+import { FileDiffView } from "./FileDiffView";
+import { stage_files } from "../actions/stage_files";
+import { unstage_files } from "../actions/unstage_files";
+import { discard_token } from "../actions/discard_token";
+import { discard_changes } from "../actions/discard_changes";
+import { hide_on_focus_lost } from "../actions/hide_on_focus_lost";
+
+interface FileListProps {
+  path: string;
+  changeSeed: number;
+  onChange: () => void;
+}
+
+export const FileList: React.FC<FileListProps> = ({
+  path,
+  changeSeed,
+  onChange,
+}) => {
+  const [files, setFiles] = React.useState<FileStatus[] | null>(null);
+  const [error, setError] = React.useState<string | null>(null);
+  const [selected, setSelected] = React.useState<string | null>(null);
+  const [seed, setSeed] = React.useState(0);
+
+  const worktreeContext = React.useContext(WorktreeStatusContext);
+
+  React.useEffect(() => {
+    let stillActive = true;
+    worktreeContext
+      .get_file_statuses(path)
+      .then((result) => {
+        if (stillActive) setFiles(result);
+      })
+      .catch((oeps: string) => {
+        if (stillActive) setError(oeps);
+      });
+    return () => {
+      stillActive = false;
+    };
+  }, [path, seed, changeSeed, worktreeContext.seed]);
+
+  async function run(operation: () => Promise<unknown>) {
+    setError(null);
+    await operation().catch((oeps: string) => setError(oeps));
+    setSeed(seed + 1);
+    onChange();
+  }
+
+  async function discard(file: string) {
+    setError(null);
+    // the token pins the confirmation to the content the user saw
+    const token = await discard_token(path, [file]).catch((oeps: string) => {
+      setError(oeps);
+      return null;
+    });
+    if (token === null) return;
+    await hide_on_focus_lost(false);
+    const confirmed = await confirm(
+      `Discard the changes to ${file}? A backup is kept in refs/worktree-status/backups.`,
+      { title: "Discard changes", kind: "warning" }
+    );
+    await hide_on_focus_lost(true);
+    if (confirmed) await run(() => discard_changes(path, [file], token));
+  }
+
+  if (files === null) {
+    if (error) return <div className="text-danger">{error}</div>;
+    return <div>Loading...</div>;
+  }
+  return (
+    <>
+      {error && <div className="text-danger">{error}</div>}
+      <ul className="list-unstyled font-monospace small mb-0 files">
+        {files.map((file) => (
+          <li key={file.path}>
+            <div className="float-end">
+              {file.worktree_status !== null && (
+                <span
+                  title="stage"
+                  style={{ cursor: "pointer" }}
+                  onClick={() => run(() => stage_files(path, [file.path]))}
+                >
+                  {" "}
+                  +
+                </span>
+              )}
+              {file.index_status !== null && (
+                <span
+                  title="unstage"
+                  style={{ cursor: "pointer" }}
+                  onClick={() => run(() => unstage_files(path, [file.path]))}
+                >
+                  {" "}
+                  −
+                </span>
+              )}
+              {file.worktree_status !== null && (
+                <span
+                  title="discard changes"
+                  className="text-danger"
+                  style={{ cursor: "pointer" }}
+                  onClick={() => discard(file.path)}
+                >
+                  {" "}
+                  ↺
+                </span>
+              )}
+            </div>
+            <div
+              className="text-truncate"
+              style={{ cursor: "pointer" }}
+              title={file.size !== null ? `${file.path} (${file.size} bytes)` : file.path}
+              onClick={() => setSelected(selected === file.path ? null : file.path)}
+            >
+              {file.index_status ?? " "}
+              {file.worktree_status ?? " "} {file.path}
+              {file.renamed_from && (
+                <span className="text-muted"> ← {file.renamed_from}</span>
+              )}
+            </div>
+            {/* a file with staged and unstaged changes (MM, AM) shows both diffs */}
+            {selected === file.path &&
+              [true, false]
+                .filter((staged) =>
+                  staged ? file.index_status !== null : file.worktree_status !== null
+                )
+                .map((staged) => (
+                  <div key={`${seed}-${staged}`}>
+                    {file.index_status !== null && file.worktree_status !== null && (
+                      <div className="text-muted">{staged ? "staged" : "unstaged"}</div>
+                    )}
+                    <FileDiffView
+                      path={path}
+                      file={file.path}
+                      staged={staged}
+                      onChange={() => {
+                        setSeed(seed + 1);
+                        onChange();
+                      }}
+                    />
+                  </div>
+                ))}
+          </li>
+        ))}
+      </ul>
+    </>
+  );
+};
+
+export default FileList;
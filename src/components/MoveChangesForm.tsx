@@ -0,0 +1,88 @@
+import React from "react";
+import Form from "react-bootstrap/Form";
+import Button from "react-bootstrap/Button";
+
+import { move_changes } from "../actions/move_changes";
+import { MoveResult } from "../types";
+
+interface MoveChangesFormProps {
+  path: string;
+  // other worktrees of the same repository
+  targets: string[];
+  onMove: () => void;
+}
+
+export const MoveChangesForm: React.FC<MoveChangesFormProps> = ({
+  path,
+  targets,
+  onMove,
+}) => {
+  const [target, setTarget] = React.useState(targets[0] ?? "");
+  const [result, setResult] = React.useState<MoveResult | null>(null);
+  const [error, setError] = React.useState<string | null>(null);
+
+  async function submit() {
+    setError(null);
+    setResult(null);
+    const result = await move_changes(path, target).catch((oeps: string) => {
+      setError(oeps);
+      return null;
+    });
+    setResult(result);
+    if (result?.moved) onMove();
+  }
+
+  if (targets.length === 0) return <></>;
+  return (
+    <Form
+      className="move-changes-form mt-1"
+      onSubmit={(e) => {
+        e.preventDefault();
+        submit();
+      }}
+    >
+      <div className="d-flex gap-1">
+        <Form.Select
+          size="sm"
+          value={target}
+          onChange={(e) => setTarget(e.target.value)}
+        >
+          {targets.map((t) => (
+            <option key={t} value={t}>
+              {t}
+            </option>
+          ))}
+        </Form.Select>
+        <Button
+          size="sm"
+          variant="secondary"
+          type="submit"
+          disabled={target === ""}
+          title="Staged, unstaged and untracked changes, they arrive unstaged"
+        >
+          Move changes
+        </Button>
+      </div>
+      {result?.moved && (
+        <div className="small text-success" title={result.files.join("\n")}>
+          Moved {result.files.length} files, backup in {result.backup}
+        </div>
+      )}
+      {result !== null && !result.moved && (
+        <div className="small text-warning">
+          Nothing moved, these don't apply to {target}:
+          <ul className="list-unstyled font-monospace mb-0">
+            {result.rejected.map((rejected, index) => (
+              <li key={index}>
+                {rejected.path} {rejected.hunk ?? "(file exists)"}
+              </li>
+            ))}
+          </ul>
+        </div>
+      )}
+      {error && <div className="text-danger">{error}</div>}
+    </Form>
+  );
+};
+
+export default MoveChangesForm;
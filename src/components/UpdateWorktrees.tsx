@@ -0,0 +1,72 @@
+import React from "react";
+import Button from "react-bootstrap/Button";
+
+import { update_worktrees } from "../actions/update_worktrees";
+import { UpdateResult } from "../types";
+
+interface UpdateWorktreesProps {
+  path: string;
+  baseRef: string | null;
+  merge: boolean;
+  onUpdate: () => void;
+}
+
+export const UpdateWorktrees: React.FC<UpdateWorktreesProps> = ({
+  path,
+  baseRef,
+  merge,
+  onUpdate,
+}) => {
+  const [updating, setUpdating] = React.useState(false);
+  const [results, setResults] = React.useState<UpdateResult[] | null>(null);
+  const [error, setError] = React.useState<string | null>(null);
+
+  async function update() {
+    setError(null);
+    setUpdating(true);
+    const results = await update_worktrees(path, baseRef, merge).catch(
+      (oeps: string) => {
+        setError(oeps);
+        return null;
+      }
+    );
+    setResults(results);
+    setUpdating(false);
+    onUpdate();
+  }
+
+  return (
+    <div className="mt-1">
+      <Button
+        size="sm"
+        variant="outline-primary"
+        disabled={updating}
+        onClick={update}
+        title="Worktrees with uncommitted changes are skipped, a conflict leaves the branch untouched"
+      >
+        {updating
+          ? "Updating..."
+          : `${merge ? "Merge" : "Rebase"} worktrees onto ${baseRef ?? "origin/HEAD"}`}
+      </Button>
+      {results !== null && (
+        <ul className="list-unstyled small mb-0">
+          {results
+            .filter((result) => result.status !== "skipped")
+            .map((result) => (
+              <li
+                key={result.path}
+                className={result.status === "updated" ? "text-success" : result.status === "up-to-date" ? "" : "text-warning"}
+                title={result.conflicts.join("\n")}
+              >
+                {result.branch ?? result.path}: {result.status}
+                {result.message && ` (${result.message})`}
+              </li>
+            ))}
+        </ul>
+      )}
+      {error && <div className="text-danger">{error}</div>}
+    </div>
+  );
+};
+
+export default UpdateWorktrees;
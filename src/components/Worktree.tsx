@@ -1,11 +1,31 @@
 import React from "react";
+import { confirm, open } from "@tauri-apps/plugin-dialog";
 import Container from "react-bootstrap/Container";
 import Row from "react-bootstrap/Row";
 import Col from "react-bootstrap/Col";
+import Button from "react-bootstrap/Button";
 
 import { WorktreeStatusContext } from "../contexts/WorktreeStatusContext";
-import { DirectoryResult, BranchState } from "../types";
+import {
+  DirectoryResult,
+  BranchState,
+  BrokenWorktree,
+  DiffTotals,
+  RepositoryError,
+} from "../types";
 import { Actions } from "./Actions";
+import { FileList } from "./FileList";
+import { CommitForm } from "./CommitForm";
+import { CreateWorktreeForm } from "./CreateWorktreeForm";
+import { BranchSwitcher } from "./BranchSwitcher";
+import { UpdateWorktrees } from "./UpdateWorktrees";
+import { MoveChangesForm } from "./MoveChangesForm";
+import { remove_worktree } from "../actions/remove_worktree";
+import { hide_on_focus_lost } from "../actions/hide_on_focus_lost";
+import { repair_worktree } from "../actions/repair_worktree";
+import { prune_worktrees } from "../actions/prune_worktrees";
+import { fast_forward } from "../actions/fast_forward";
+import { predict_conflicts } from "../actions/predict_conflicts";
 
 interface StateProps {
   name: string;
@@ -13,6 +33,22 @@ interface StateProps {
   count: number;
 }
 
+const DiffState: React.FC<{ name: string; diff: DiffTotals }> = ({
+  name,
+  diff,
+}) => {
+  if (diff.files_changed === 0) return <></>;
+  return (
+    <span
+      className="diff"
+      title={`${name}: ${diff.files_changed} files, ${diff.insertions} insertions, ${diff.deletions} deletions`}
+    >
+      <span className="text-success">+{diff.insertions}</span>
+      <span className="text-danger">-{diff.deletions}</span>
+    </span>
+  );
+};
+
 const State: React.FC<StateProps> = ({ name, icon, count }) => {
   if (count === 0) return <></>;
   return (
@@ -23,11 +59,41 @@ const State: React.FC<StateProps> = ({ name, icon, count }) => {
   );
 };
 
-export const Worktree: React.FC<DirectoryResult> = ({ name, path }) => {
+interface WorktreeProps extends DirectoryResult {
+  baseRef: string | null;
+  fetchSeed: number;
+  worktreeTemplate: string | null;
+  updateWithMerge: boolean;
+  // paths of the other worktrees of the same repository
+  siblings: string[];
+  onWorktreesChanged: () => void;
+}
+
+export const Worktree: React.FC<WorktreeProps> = ({
+  name,
+  path,
+  relative_path,
+  main_repository,
+  locked,
+  lock_reason,
+  baseRef,
+  fetchSeed,
+  worktreeTemplate,
+  updateWithMerge,
+  siblings,
+  onWorktreesChanged,
+}) => {
   const [branchState, setBranchState] = React.useState<BranchState | null>(
     null
   );
   const [error, setError] = React.useState<string | null>(null);
+  const [broken, setBroken] = React.useState<BrokenWorktree | null>(null);
+  const [showFiles, setShowFiles] = React.useState(false);
+  const [changeSeed, setChangeSeed] = React.useState(0);
+  const [baseConflicts, setBaseConflicts] = React.useState<string[]>([]);
+  const [conflictsError, setConflictsError] = React.useState<string | null>(
+    null
+  );
 
   const worktreeContext = React.useContext(WorktreeStatusContext);
 
@@ -35,54 +101,363 @@ export const Worktree: React.FC<DirectoryResult> = ({ name, path }) => {
     var setState = true;
     async function getBranchState(path: string) {
       const state = (await worktreeContext
-        .get_branch_state(path)
-        .catch((oeps: string) => setError(oeps))) as BranchState;
+        .get_branch_state(path, baseRef)
+        .catch((oeps: RepositoryError) => {
+          if (!setState) return;
+          setError(oeps.message);
+          setBroken(oeps.kind === "broken_worktree" ? oeps.worktree : null);
+        })) as BranchState;
       if (state !== undefined) {
-        if (setState) setBranchState(state);
+        if (setState) {
+          setBranchState(state);
+          setError(null);
+          setBroken(null);
+        }
       }
     }
     getBranchState(path);
     return () => {
       setState = false;
     };
-  }, [path, worktreeContext.seed]);
+  }, [path, baseRef, fetchSeed, changeSeed, worktreeContext.seed]);
+
+  // a merge in memory is too slow to run on every focus, only redo it when either side moved
+  const diverged =
+    branchState !== null &&
+    branchState.base_ahead > 0 &&
+    branchState.base_behind > 0;
+  React.useEffect(() => {
+    setBaseConflicts([]);
+    setConflictsError(null);
+    if (!diverged) return;
+    var setState = true;
+    predict_conflicts(path, baseRef)
+      .then((conflicts) => {
+        if (setState) setBaseConflicts(conflicts);
+      })
+      .catch((oeps: string) => {
+        if (setState) setConflictsError(oeps);
+      });
+    return () => {
+      setState = false;
+    };
+  }, [
+    path,
+    baseRef,
+    diverged,
+    branchState?.commit_id,
+    branchState?.base_behind,
+  ]);
+
+  async function repairWorktree() {
+    let mainRepository: string | null = null;
+    // the main repository was moved, only the user knows where to
+    if (broken?.problem === "missing-gitdir") {
+      await hide_on_focus_lost(false);
+      mainRepository = await open({
+        multiple: false,
+        directory: true,
+        title: "Select the new location of the main repository",
+      });
+      await hide_on_focus_lost(true);
+      if (mainRepository === null) return;
+    }
+    await repair_worktree(path, mainRepository)
+      .then(() => setChangeSeed(changeSeed + 1))
+      .catch((oeps: string) => setError(oeps));
+  }
+
+  async function pruneWorktrees() {
+    await hide_on_focus_lost(false);
+    const confirmed = await confirm(
+      `Remove the metadata of ${branchState?.stale_worktrees.join(", ")}? Worktrees that were moved instead of deleted should be repaired first.`,
+      { title: "Prune worktrees", kind: "warning" }
+    );
+    await hide_on_focus_lost(true);
+    if (!confirmed) return;
+    await prune_worktrees(path)
+      .then(onWorktreesChanged)
+      .catch((oeps: string) => setError(oeps));
+  }
+
+  async function fastForward() {
+    setError(null);
+    const result = await fast_forward(path);
+    if (result.status !== "updated" && result.status !== "up-to-date")
+      setError(result.status + (result.message ? ": " + result.message : ""));
+    setChangeSeed(changeSeed + 1);
+  }
+
+  async function removeWorktree() {
+    const branch = branchState?.head_kind === "branch" ? branchState.branch : null;
+    await hide_on_focus_lost(false);
+    const confirmed = await confirm(`Remove the worktree at ${path}?`, {
+      title: "Remove worktree",
+      kind: "warning",
+    });
+    const deleteBranch =
+      confirmed &&
+      branch !== null &&
+      (await confirm(`Also delete the branch ${branch}?`, {
+        title: "Remove worktree",
+      }));
+    await hide_on_focus_lost(true);
+    if (!confirmed) return;
+
+    setError(null);
+    await remove_worktree(path, baseRef, deleteBranch)
+      .then(onWorktreesChanged)
+      .catch((oeps: string) => setError(oeps));
+  }
 
   return (
     <Container fluid className="p-1 worktree">
       <Row>
         <Col>
-          <h3 title={path} aria-label={path}>
-            {name}
+          <h3
+            title={main_repository ? `${path}\nworktree of ${main_repository}` : path}
+            aria-label={path}
+          >
+            {relative_path || name}
+            {locked && (
+              <span title={lock_reason ? "locked: " + lock_reason : "locked"}>
+                {" "}
+                🔒
+              </span>
+            )}
           </h3>
         </Col>
         <Col className="state">
           {branchState !== null && (
             <>
-              <div title={branchState.branch} aria-label={branchState.branch}>
+              <div
+                title={[
+                  branchState.branch,
+                  branchState.describe,
+                  branchState.last_fetched &&
+                    "last fetched " +
+                      new Date(branchState.last_fetched * 1000).toLocaleString(),
+                ]
+                  .filter((s) => s)
+                  .join("\n")}
+                aria-label={branchState.branch}
+              >
+                {branchState.head_kind === "tag" && "🏷 "}
                 {branchState.branch}
+                {branchState.head_kind === "detached" &&
+                  branchState.describe &&
+                  ` (${branchState.describe})`}
+                {branchState.head_kind === "unborn" && " (no commits)"}
+                {branchState.upstream.status === "gone" && (
+                  <span
+                    className="text-danger"
+                    title={branchState.upstream.name + " no longer exists"}
+                  >
+                    {" "}
+                    (gone)
+                  </span>
+                )}
               </div>
-              <div>
+              {branchState.state !== "clean" && (
+                <div className="text-warning" title={"operation in progress: " + branchState.state}>
+                  {branchState.state}
+                  {branchState.state_step !== null &&
+                    branchState.state_total !== null &&
+                    ` ${branchState.state_step}/${branchState.state_total}`}
+                </div>
+              )}
+              <div
+                style={{ cursor: "pointer" }}
+                onClick={() => setShowFiles(!showFiles)}
+              >
                 <State name="behind" icon="↓" count={branchState.behind} />
                 <State name="ahead" icon="↑" count={branchState.ahead} />
-                <State name="staged" icon="✔" count={branchState.staged} />
-                {/* <State name="added" icon="+" count={branchState.added} /> */}
-                <State name="modified" icon="±" count={branchState.modified} />
-                <State name="deleted" icon="⦸" count={branchState.deleted} />
+                <State
+                  name={"behind " + branchState.base_ref}
+                  icon="⇣"
+                  count={branchState.base_behind}
+                />
+                <State
+                  name={"ahead of " + branchState.base_ref}
+                  icon="⇡"
+                  count={branchState.base_ahead}
+                />
+                {/* staged files that aren't counted as added, renamed or type changed below */}
+                <State
+                  name="staged"
+                  icon="✔"
+                  count={branchState.index.modified + branchState.index.deleted}
+                />
+                <State name="added" icon="+" count={branchState.index.new} />
+                <State
+                  name="renamed"
+                  icon="»"
+                  count={branchState.index.renamed + branchState.worktree.renamed}
+                />
+                <State
+                  name="type changed"
+                  icon="T"
+                  count={
+                    branchState.index.typechange + branchState.worktree.typechange
+                  }
+                />
+                <State
+                  name="modified"
+                  icon="±"
+                  count={branchState.worktree.modified}
+                />
+                <State
+                  name="deleted"
+                  icon="⦸"
+                  count={branchState.worktree.deleted}
+                />
                 <State
                   name="untracked"
                   icon="?"
-                  count={branchState.untracked}
+                  count={branchState.worktree.new}
                 />
                 <State name="conflict" icon="⨂" count={branchState.conflict} />
+                {baseConflicts.length > 0 && (
+                  <span
+                    className="text-warning"
+                    title={`${baseConflicts.length} files would conflict with ${branchState.base_ref}:\n${baseConflicts.join("\n")}`}
+                  >
+                    {baseConflicts.length}⚠
+                  </span>
+                )}
+                {conflictsError && (
+                  <span
+                    className="text-warning"
+                    title={"Could not check for conflicts: " + conflictsError}
+                  >
+                    ?⚠
+                  </span>
+                )}
+                <State
+                  name={"stashes on " + branchState.branch}
+                  icon="⚑"
+                  count={branchState.stashes}
+                />
               </div>
+              <div>
+                <DiffState name="staged" diff={branchState.staged_diff} />
+                <DiffState name="unstaged" diff={branchState.unstaged_diff} />
+              </div>
+              {branchState.last_commit && (
+                <div
+                  className="text-muted text-truncate"
+                  title={`${branchState.last_commit.id} by ${
+                    branchState.last_commit.author
+                  }, ${new Date(
+                    branchState.last_commit.time * 1000
+                  ).toLocaleString()}`}
+                >
+                  {branchState.last_commit.summary}
+                </div>
+              )}
+              {branchState.bare && (
+                <div className="text-muted" title={branchState.branches.join("\n")}>
+                  bare, {branchState.branches.length} branches
+                </div>
+              )}
+              {branchState.stale_worktrees.length > 0 && (
+                <div
+                  className="text-warning"
+                  title={branchState.stale_worktrees.join("\n")}
+                  style={{ cursor: "pointer" }}
+                  onClick={pruneWorktrees}
+                >
+                  {branchState.stale_worktrees.length} stale worktrees, prune
+                </div>
+              )}
             </>
           ) || "Loading..."}
           {error && <>{error}</>}
+          {broken && (
+            <div>
+              <Button size="sm" variant="warning" onClick={repairWorktree}>
+                Repair
+              </Button>
+            </div>
+          )}
         </Col>
         <Col className="me-auto text-end">
           <Actions path={path} />
         </Col>
       </Row>
+      {showFiles && (
+        <Row>
+          <Col>
+            <FileList
+              path={path}
+              changeSeed={changeSeed}
+              onChange={() => setChangeSeed(changeSeed + 1)}
+            />
+            {branchState !== null && branchState.conflict === 0 && (
+              <CommitForm
+                path={path}
+                onCommit={() => setChangeSeed(changeSeed + 1)}
+              />
+            )}
+            {branchState !== null &&
+              branchState.behind > 0 &&
+              branchState.ahead === 0 && (
+                <Button
+                  className="mt-1 me-1"
+                  size="sm"
+                  variant="outline-primary"
+                  onClick={fastForward}
+                >
+                  Fast-forward {branchState.behind} commits
+                </Button>
+              )}
+            {branchState !== null &&
+              branchState.staged +
+                branchState.added +
+                branchState.modified +
+                branchState.deleted +
+                branchState.untracked >
+                0 && (
+                <MoveChangesForm
+                  path={path}
+                  targets={siblings}
+                  onMove={onWorktreesChanged}
+                />
+              )}
+            {branchState !== null && !branchState.bare && (
+              <BranchSwitcher
+                path={path}
+                changeSeed={changeSeed}
+                onSwitch={() => setChangeSeed(changeSeed + 1)}
+              />
+            )}
+            {!main_repository && (
+              <UpdateWorktrees
+                path={path}
+                baseRef={baseRef}
+                merge={updateWithMerge}
+                onUpdate={onWorktreesChanged}
+              />
+            )}
+            <CreateWorktreeForm
+              path={path}
+              baseRef={baseRef}
+              template={worktreeTemplate}
+              onCreate={onWorktreesChanged}
+            />
+            {main_repository && (
+              <Button
+                className="mt-1"
+                size="sm"
+                variant="outline-danger"
+                onClick={removeWorktree}
+              >
+                Remove worktree
+              </Button>
+            )}
+          </Col>
+        </Row>
+      )}
     </Container>
   );
 };
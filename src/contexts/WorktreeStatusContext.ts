@@ -1,13 +1,32 @@
 import React from "react";
-import { DirectoryResult, BranchState } from "../types";
+import {
+  DirectoryResult,
+  BranchState,
+  FetchResult,
+  FileDiff,
+  FileStatus,
+} from "../types";
 
 interface WorktreeStatusContextType {
   seed: number;
   scan_directory: (
     path: string,
-    filter: string | null
+    filter: string | null,
+    discoverWorktrees: boolean,
+    maxDepth: number | null,
+    skipDirectories: string[] | null
   ) => Promise<DirectoryResult[]>;
-  get_branch_state: (path: string) => Promise<BranchState | null>;
+  get_branch_state: (
+    path: string,
+    baseRef: string | null
+  ) => Promise<BranchState | null>;
+  fetch_remotes: (path: string) => Promise<FetchResult[]>;
+  get_file_statuses: (path: string) => Promise<FileStatus[]>;
+  get_file_diff: (
+    path: string,
+    file: string,
+    staged: boolean
+  ) => Promise<FileDiff | null>;
 }
 
 export const WorktreeStatusContext =
@@ -16,7 +35,16 @@ export const WorktreeStatusContext =
     scan_directory: async (_path: string) => {
       return [];
     },
-    get_branch_state: async (_path: string) => {
+    get_branch_state: async (_path: string, _baseRef: string | null) => {
+      return null;
+    },
+    fetch_remotes: async (_path: string) => {
+      return [];
+    },
+    get_file_statuses: async (_path: string) => {
+      return [];
+    },
+    get_file_diff: async (_path: string, _file: string, _staged: boolean) => {
       return null;
     },
   });
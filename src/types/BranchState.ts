@@ -1,7 +1,23 @@
+import { CommitInfo } from "./CommitInfo";
+import { DiffTotals } from "./DiffTotals";
+import { StatusCounts } from "./StatusCounts";
+
 export interface BranchState {
   branch: string;
+  head_kind: "branch" | "detached" | "tag" | "unborn";
+  upstream: {
+    name: string | null;
+    status: "tracking" | "gone" | "none";
+  };
+  commit_id: string | null;
+  describe: string | null;
+  last_commit: CommitInfo | null;
   ahead: number;
   behind: number;
+  base_ref: string | null;
+  base_ahead: number;
+  base_behind: number;
+  last_fetched: number | null;
 
   staged: number;
   added: number;
@@ -9,6 +25,19 @@ export interface BranchState {
   deleted: number;
   untracked: number;
   conflict: number;
+  index: StatusCounts;
+  worktree: StatusCounts;
+  staged_diff: DiffTotals;
+  unstaged_diff: DiffTotals;
+  stashes: number;
+
+  bare: boolean;
+  branches: string[];
+  stale_worktrees: string[];
+
+  state: string;
+  state_step: number | null;
+  state_total: number | null;
 }
 
 export default BranchState;
@@ -0,0 +1,32 @@
+export type BulkOperation =
+  | { kind: "fetch" }
+  | { kind: "fast-forward" }
+  | { kind: "prune" }
+  | { kind: "gc" }
+  // split into words by the backend like a shell does
+  | { kind: "custom"; command: string };
+
+export interface BulkResult {
+  path: string;
+  // ok or failed, fast-forward reports its own status
+  status: string;
+  message: string | null;
+}
+
+export interface BulkProgress {
+  // started or finished
+  event: string;
+  path: string;
+  completed: number;
+  total: number;
+  result: BulkResult | null;
+}
+
+export interface BulkReport {
+  operation: string;
+  total: number;
+  counts: Record<string, number>;
+  results: BulkResult[];
+}
+
+export default BulkReport;
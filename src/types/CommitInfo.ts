@@ -0,0 +1,8 @@
+export interface CommitInfo {
+  id: string;
+  summary: string;
+  author: string;
+  time: number;
+}
+
+export default CommitInfo;
@@ -0,0 +1,7 @@
+export interface DiffTotals {
+  files_changed: number;
+  insertions: number;
+  deletions: number;
+}
+
+export default DiffTotals;
@@ -1,6 +1,11 @@
 export interface DirectoryResult {
   name: string;
   path: string;
+  relative_path: string;
+  main_repository: string | null;
+  locked: boolean;
+  lock_reason: string | null;
+  bare: boolean;
 }
 
 export default DirectoryResult;
@@ -0,0 +1,9 @@
+export interface FastForwardResult {
+  path: string;
+  // updated, up-to-date, diverged, dirty, no-upstream or error
+  status: string;
+  message: string | null;
+  commits: number;
+}
+
+export default FastForwardResult;
@@ -0,0 +1,8 @@
+export interface FetchResult {
+  remote: string;
+  received_objects: number;
+  received_bytes: number;
+  error: string | null;
+}
+
+export default FetchResult;
@@ -0,0 +1,24 @@
+export interface DiffLine {
+  origin: " " | "+" | "-" | string;
+  old_lineno: number | null;
+  new_lineno: number | null;
+  content: string;
+}
+
+export interface DiffHunk {
+  header: string;
+  old_start: number;
+  old_lines: number;
+  new_start: number;
+  new_lines: number;
+  lines: DiffLine[];
+}
+
+export interface FileDiff {
+  path: string;
+  binary: boolean;
+  hunks: DiffHunk[];
+  patch: string;
+}
+
+export default FileDiff;
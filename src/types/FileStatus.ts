@@ -0,0 +1,9 @@
+export interface FileStatus {
+  path: string;
+  index_status: string | null;
+  worktree_status: string | null;
+  renamed_from: string | null;
+  size: number | null;
+}
+
+export default FileStatus;
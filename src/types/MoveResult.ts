@@ -0,0 +1,15 @@
+export interface RejectedHunk {
+  path: string;
+  // hunk header, null when the whole file can't be applied
+  hunk: string | null;
+}
+
+export interface MoveResult {
+  // false when hunks were rejected, neither worktree is changed then
+  moved: boolean;
+  files: string[];
+  rejected: RejectedHunk[];
+  backup: string | null;
+}
+
+export default MoveResult;
@@ -0,0 +1,11 @@
+export interface BrokenWorktree {
+  // missing-gitdir or moved
+  problem: string;
+  gitdir: string;
+}
+
+export type RepositoryError =
+  | { kind: "broken_worktree"; message: string; worktree: BrokenWorktree }
+  | { kind: "other"; message: string };
+
+export default RepositoryError;
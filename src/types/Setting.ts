@@ -1,7 +1,7 @@
 export interface Setting {
   key: string;
   displayName: string;
-  type: "bool" | "string";
+  type: "bool" | "string" | "number";
   value: string;
 }
 
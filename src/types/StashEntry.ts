@@ -0,0 +1,8 @@
+export interface StashEntry {
+  index: number;
+  message: string;
+  branch: string | null;
+  time: number;
+}
+
+export default StashEntry;
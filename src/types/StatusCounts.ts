@@ -0,0 +1,9 @@
+export interface StatusCounts {
+  new: number;
+  modified: number;
+  deleted: number;
+  renamed: number;
+  typechange: number;
+}
+
+export default StatusCounts;
@@ -0,0 +1,10 @@
+export interface UpdateResult {
+  path: string;
+  branch: string | null;
+  // updated, up-to-date, dirty, conflict, skipped or error
+  status: string;
+  message: string | null;
+  conflicts: string[];
+}
+
+export default UpdateResult;
@@ -4,4 +4,12 @@ export interface WorktreePath {
   displayName: string | null;
   filter: string | null;
   defaultCollapse: boolean;
+  discoverWorktrees: boolean;
+  maxDepth: number | null;
+  skipDirectories: string[] | null;
+  baseRef: string | null;
+  fetchInterval: number | null;
+  worktreeTemplate: string | null;
+  // update feature worktrees by merging the base branch instead of rebasing onto it
+  updateWithMerge: boolean;
 }
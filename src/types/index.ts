@@ -1,6 +1,23 @@
 export type { Action } from "./Action";
 export type { BranchState } from "./BranchState";
+export type {
+  BulkOperation,
+  BulkProgress,
+  BulkReport,
+  BulkResult,
+} from "./BulkReport";
+export type { CommitInfo } from "./CommitInfo";
+export type { DiffTotals } from "./DiffTotals";
 export type { Config } from "./Config";
 export type { DirectoryResult } from "./DirectoryResult";
+export type { FastForwardResult } from "./FastForwardResult";
+export type { FetchResult } from "./FetchResult";
+export type { DiffHunk, DiffLine, FileDiff } from "./FileDiff";
+export type { FileStatus } from "./FileStatus";
+export type { BrokenWorktree, RepositoryError } from "./RepositoryError";
+export type { MoveResult, RejectedHunk } from "./MoveResult";
 export type { Setting } from "./Setting";
+export type { StashEntry } from "./StashEntry";
+export type { StatusCounts } from "./StatusCounts";
+export type { UpdateResult } from "./UpdateResult";
 export type { WorktreePath } from "./WorktreePath";